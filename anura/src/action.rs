@@ -0,0 +1,472 @@
+use std::{
+    fmt,
+    fs,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+use crate::error::AnuraError;
+
+/// Names keys can be written as in shortcuts, the first name of a key is used when printing it
+const KEY_NAMES: &[(&str, VirtualKeyCode)] = &[
+    ("A", VirtualKeyCode::A), ("B", VirtualKeyCode::B), ("C", VirtualKeyCode::C),
+    ("D", VirtualKeyCode::D), ("E", VirtualKeyCode::E), ("F", VirtualKeyCode::F),
+    ("G", VirtualKeyCode::G), ("H", VirtualKeyCode::H), ("I", VirtualKeyCode::I),
+    ("J", VirtualKeyCode::J), ("K", VirtualKeyCode::K), ("L", VirtualKeyCode::L),
+    ("M", VirtualKeyCode::M), ("N", VirtualKeyCode::N), ("O", VirtualKeyCode::O),
+    ("P", VirtualKeyCode::P), ("Q", VirtualKeyCode::Q), ("R", VirtualKeyCode::R),
+    ("S", VirtualKeyCode::S), ("T", VirtualKeyCode::T), ("U", VirtualKeyCode::U),
+    ("V", VirtualKeyCode::V), ("W", VirtualKeyCode::W), ("X", VirtualKeyCode::X),
+    ("Y", VirtualKeyCode::Y), ("Z", VirtualKeyCode::Z),
+    ("0", VirtualKeyCode::Key0), ("1", VirtualKeyCode::Key1), ("2", VirtualKeyCode::Key2),
+    ("3", VirtualKeyCode::Key3), ("4", VirtualKeyCode::Key4), ("5", VirtualKeyCode::Key5),
+    ("6", VirtualKeyCode::Key6), ("7", VirtualKeyCode::Key7), ("8", VirtualKeyCode::Key8),
+    ("9", VirtualKeyCode::Key9),
+    ("F1", VirtualKeyCode::F1), ("F2", VirtualKeyCode::F2), ("F3", VirtualKeyCode::F3),
+    ("F4", VirtualKeyCode::F4), ("F5", VirtualKeyCode::F5), ("F6", VirtualKeyCode::F6),
+    ("F7", VirtualKeyCode::F7), ("F8", VirtualKeyCode::F8), ("F9", VirtualKeyCode::F9),
+    ("F10", VirtualKeyCode::F10), ("F11", VirtualKeyCode::F11), ("F12", VirtualKeyCode::F12),
+    ("Escape", VirtualKeyCode::Escape), ("Esc", VirtualKeyCode::Escape),
+    ("Enter", VirtualKeyCode::Return), ("Return", VirtualKeyCode::Return),
+    ("Space", VirtualKeyCode::Space),
+    ("Tab", VirtualKeyCode::Tab),
+    ("Backspace", VirtualKeyCode::Back),
+    ("Delete", VirtualKeyCode::Delete), ("Del", VirtualKeyCode::Delete),
+    ("Insert", VirtualKeyCode::Insert),
+    ("Home", VirtualKeyCode::Home), ("End", VirtualKeyCode::End),
+    ("PageUp", VirtualKeyCode::PageUp), ("PageDown", VirtualKeyCode::PageDown),
+    ("Left", VirtualKeyCode::Left), ("Right", VirtualKeyCode::Right),
+    ("Up", VirtualKeyCode::Up), ("Down", VirtualKeyCode::Down),
+    ("-", VirtualKeyCode::Minus), ("=", VirtualKeyCode::Equals),
+    (",", VirtualKeyCode::Comma), (".", VirtualKeyCode::Period),
+    ("/", VirtualKeyCode::Slash), ("\\", VirtualKeyCode::Backslash),
+    (";", VirtualKeyCode::Semicolon), ("'", VirtualKeyCode::Apostrophe),
+    ("[", VirtualKeyCode::LBracket), ("]", VirtualKeyCode::RBracket),
+    ("`", VirtualKeyCode::Grave), ("+", VirtualKeyCode::Plus),
+];
+
+fn is_modifier_key(key: VirtualKeyCode) -> bool {
+    matches!(
+        key,
+        VirtualKeyCode::LShift | VirtualKeyCode::RShift
+            | VirtualKeyCode::LControl | VirtualKeyCode::RControl
+            | VirtualKeyCode::LAlt | VirtualKeyCode::RAlt
+            | VirtualKeyCode::LWin | VirtualKeyCode::RWin
+    )
+}
+
+/// A key pressed together with modifiers, e.g. `Ctrl+Shift+P`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: VirtualKeyCode,
+    pub modifiers: ModifiersState,
+}
+
+impl KeyChord {
+    pub fn new(key: VirtualKeyCode) -> Self {
+        Self { key, modifiers: ModifiersState::empty() }
+    }
+
+    pub fn ctrl(mut self) -> Self {
+        self.modifiers |= ModifiersState::CTRL;
+        self
+    }
+
+    pub fn shift(mut self) -> Self {
+        self.modifiers |= ModifiersState::SHIFT;
+        self
+    }
+
+    pub fn alt(mut self) -> Self {
+        self.modifiers |= ModifiersState::ALT;
+        self
+    }
+
+    /// The windows key or cmd on macos
+    pub fn super_key(mut self) -> Self {
+        self.modifiers |= ModifiersState::LOGO;
+        self
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = ParseShortcutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |part: &str| ParseShortcutError { part: part.to_owned(), shortcut: s.to_owned() };
+
+        // The key comes last, splitting from the right keeps `Ctrl++` working
+        let (modifiers, key) = match s.trim().rsplit_once('+') {
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", s.trim()),
+        };
+
+        let key = KEY_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, key)| *key)
+            .ok_or_else(|| error(key))?;
+
+        let mut chord = KeyChord::new(key);
+
+        for modifier in modifiers.split('+').map(str::trim).filter(|m| !m.is_empty()) {
+            chord = match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl(),
+                "shift" => chord.shift(),
+                "alt" | "option" => chord.alt(),
+                "super" | "cmd" | "meta" | "win" | "logo" => chord.super_key(),
+                _ => return Err(error(modifier)),
+            };
+        }
+
+        Ok(chord)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl() { write!(f, "Ctrl+")? }
+        if self.modifiers.shift() { write!(f, "Shift+")? }
+        if self.modifiers.alt() { write!(f, "Alt+")? }
+        if self.modifiers.logo() { write!(f, "Super+")? }
+
+        match KEY_NAMES.iter().find(|(_, key)| *key == self.key) {
+            Some((name, _)) => write!(f, "{name}"),
+            None => write!(f, "{:?}", self.key),
+        }
+    }
+}
+
+/// One or more chords which have to be pressed one after another, e.g. `Ctrl+K Ctrl+S`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut(pub Vec<KeyChord>);
+
+impl From<KeyChord> for Shortcut {
+    fn from(chord: KeyChord) -> Self {
+        Shortcut(vec![chord])
+    }
+}
+
+impl From<VirtualKeyCode> for Shortcut {
+    fn from(key: VirtualKeyCode) -> Self {
+        KeyChord::new(key).into()
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = ParseShortcutError;
+
+    /// Chords are separated by spaces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chords = s
+            .split_whitespace()
+            .map(KeyChord::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match chords.is_empty() {
+            true => Err(ParseShortcutError { part: String::new(), shortcut: s.to_owned() }),
+            false => Ok(Shortcut(chords)),
+        }
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 { write!(f, " ")? }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseShortcutError {
+    /// The key or modifier which wasn't recognized, empty if the shortcut was
+    pub part: String,
+    pub shortcut: String,
+}
+
+impl fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.part.is_empty() {
+            true => write!(f, "empty shortcut"),
+            false => write!(f, "unknown key or modifier `{}` in shortcut `{}`", self.part, self.shortcut),
+        }
+    }
+}
+
+impl std::error::Error for ParseShortcutError {}
+
+/// A line of an action config which couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionConfigError {
+    /// Starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ActionConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ActionConfigError {}
+
+/// Who gets to handle an action, shortcuts bound in more specific scopes win
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ActionScope {
+    /// Sent to the app as `AppEvent::Action`
+    #[default]
+    App,
+    /// Sent to the current view as `ViewEvent::Action`
+    View,
+    /// Sent to the focused widget as `WidgetEvent::Action`, only active while a widget is focused
+    Focused,
+}
+
+impl ActionScope {
+    fn precedence(self) -> u8 {
+        match self {
+            ActionScope::App => 0,
+            ActionScope::View => 1,
+            ActionScope::Focused => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub action: String,
+    pub shortcut: Shortcut,
+    pub scope: ActionScope,
+}
+
+/// Named actions bound to shortcuts, reachable through `AnuraApp::actions`
+///
+/// Key presses which trigger an action (or are part of a sequence which might)
+/// are taken out of `InputManager::key_presses` so widgets dont handle them as well
+#[derive(Debug, Clone)]
+pub struct ActionMap {
+    bindings: Vec<Binding>,
+    /// Chords of a sequence pressed so far
+    pending: Vec<KeyChord>,
+    /// See `InputManager::now`
+    pending_since: Option<Duration>,
+    /// How long to wait for the next chord of a sequence before starting over
+    pub sequence_timeout: Duration,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            pending: Vec::new(),
+            pending_since: None,
+            sequence_timeout: Duration::from_millis(1500),
+        }
+    }
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a shortcut for the action, an action can have any number of them
+    pub fn bind(&mut self, scope: ActionScope, action: impl Into<String>, shortcut: impl Into<Shortcut>) {
+        let binding = Binding { action: action.into(), shortcut: shortcut.into(), scope };
+
+        if !self.bindings.contains(&binding) {
+            self.bindings.push(binding);
+        }
+    }
+
+    /// Removes every shortcut of the action
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.retain(|binding| binding.action != action);
+    }
+
+    /// Replaces every shortcut of the action with `shortcut`, the scope stays the same
+    /// (`ActionScope::App` if the action wasn't bound yet)
+    pub fn rebind(&mut self, action: &str, shortcut: impl Into<Shortcut>) {
+        let scope = self.scope_of(action).unwrap_or_default();
+
+        self.unbind(action);
+        self.bind(scope, action, shortcut);
+    }
+
+    pub fn scope_of(&self, action: &str) -> Option<ActionScope> {
+        self.bindings.iter().find(|binding| binding.action == action).map(|binding| binding.scope)
+    }
+
+    pub fn shortcuts<'a>(&'a self, action: &'a str) -> impl Iterator<Item = &'a Shortcut> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.action == action)
+            .map(|binding| &binding.shortcut)
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+        self.pending.clear();
+    }
+
+    /// Reads bindings from a config, actions in it lose the shortcuts they had before
+    ///
+    /// ```text
+    /// # Scopes are app, view or focused, bindings before the first one are app wide
+    /// [app]
+    /// quit = Ctrl+Q
+    ///
+    /// [view]
+    /// # Multiple shortcuts are separated by commas
+    /// save = Ctrl+S, Ctrl+K S
+    /// ```
+    pub fn load_str(&mut self, config: &str) -> Result<(), ActionConfigError> {
+        let mut scope = ActionScope::App;
+        let mut bindings = Vec::new();
+
+        for (i, line) in config.lines().enumerate() {
+            let error = |message: String| ActionConfigError { line: i + 1, message };
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                scope = match section.trim().to_ascii_lowercase().as_str() {
+                    "app" => ActionScope::App,
+                    "view" => ActionScope::View,
+                    "focused" => ActionScope::Focused,
+                    other => return Err(error(format!("unknown scope `{other}`"))),
+                };
+                continue;
+            }
+
+            let (action, shortcuts) = match line.split_once('=') {
+                Some((action, shortcuts)) if !action.trim().is_empty() => (action.trim(), shortcuts),
+                _ => return Err(error(format!("expected `action = shortcut`, got `{line}`"))),
+            };
+
+            for shortcut in shortcuts.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let shortcut = shortcut.parse::<Shortcut>().map_err(|err| error(err.to_string()))?;
+                bindings.push(Binding { action: action.to_owned(), shortcut, scope });
+            }
+        }
+
+        // Only touch the map once the whole config turned out fine
+        for binding in &bindings {
+            self.unbind(&binding.action);
+        }
+        for Binding { action, shortcut, scope } in bindings {
+            self.bind(scope, action, shortcut);
+        }
+
+        Ok(())
+    }
+
+    /// Same as `load_str` for a file on disk
+    pub fn load_file<T>(&mut self, path: impl AsRef<Path>) -> Result<(), AnuraError<T>> {
+        let path = path.as_ref();
+
+        let config = fs::read_to_string(path).map_err(|err| AnuraError::AssetNotFound {
+            asset: path.display().to_string(),
+            source: Some(err),
+        })?;
+
+        self.load_str(&config).map_err(|err| AnuraError::DecodeFailure {
+            asset: path.display().to_string(),
+            source: Some(Box::new(err)),
+        })
+    }
+
+    /// The binding with the highest precedence for `chords`, and whether a longer
+    /// sequence starting with them exists
+    fn lookup(&self, chords: &[KeyChord], focused: bool) -> (Option<&Binding>, bool) {
+        let active = self.bindings
+            .iter()
+            .filter(|binding| focused || binding.scope != ActionScope::Focused);
+
+        let mut found: Option<&Binding> = None;
+        let mut longer = false;
+
+        for binding in active {
+            let sequence = &binding.shortcut.0;
+
+            if sequence.as_slice() == chords {
+                if found.is_none_or(|found| binding.scope.precedence() > found.scope.precedence()) {
+                    found = Some(binding);
+                }
+            } else if sequence.starts_with(chords) {
+                longer = true;
+            }
+        }
+
+        (found, longer)
+    }
+
+    /// Matches this frame's key presses against the bindings, returns the triggered actions
+    /// and removes the presses they used from `key_presses`
+    pub(crate) fn process(
+        &mut self,
+        key_presses: &mut Vec<VirtualKeyCode>,
+        modifiers: ModifiersState,
+        focused: bool,
+        now: Duration,
+    ) -> Vec<(ActionScope, String)> {
+        if self.pending_since.is_some_and(|since| now.saturating_sub(since) > self.sequence_timeout) {
+            self.pending.clear();
+            self.pending_since = None;
+        }
+
+        let mut triggered = Vec::new();
+        let mut unused = Vec::new();
+
+        for key in key_presses.drain(..) {
+            if is_modifier_key(key) || self.bindings.is_empty() {
+                unused.push(key);
+                continue;
+            }
+
+            let chord = KeyChord { key, modifiers };
+
+            let mut chords = self.pending.clone();
+            chords.push(chord);
+
+            // A broken sequence gets another chance with just the last chord
+            let (binding, longer) = match self.lookup(&chords, focused) {
+                (None, false) if !self.pending.is_empty() => {
+                    chords = vec![chord];
+                    self.lookup(&chords, focused)
+                }
+                result => result,
+            };
+
+            // Exact matches win over waiting for a longer sequence
+            if let Some(binding) = binding {
+                triggered.push((binding.scope, binding.action.clone()));
+                self.pending.clear();
+                self.pending_since = None;
+            } else if longer {
+                self.pending = chords;
+                self.pending_since = Some(now);
+            } else {
+                self.pending.clear();
+                self.pending_since = None;
+                unused.push(key);
+            }
+        }
+
+        *key_presses = unused;
+        triggered
+    }
+}
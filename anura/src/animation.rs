@@ -0,0 +1,624 @@
+use std::{time::Duration, f32::consts::PI};
+
+use ahash::AHashMap;
+
+use crate::{
+    app::App,
+    graphics::AnuraRenderer,
+    handle::{TypedHandle, UntypedHandle, AsUntypedHandle},
+    uitree::UiTree,
+    view::View,
+    widget::Widget,
+};
+
+/// Anything that can be interpolated between two values
+///
+/// `t` is not clamped to 0..1 so springs and overshooting easings can go past the target
+pub trait Animatable: Clone + 'static {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Animatable for (f32, f32) {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (self.0.lerp(&to.0, t), self.1.lerp(&to.1, t))
+    }
+}
+
+impl Animatable for (f32, f32, f32) {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (self.0.lerp(&to.0, t), self.1.lerp(&to.1, t), self.2.lerp(&to.2, t))
+    }
+}
+
+// Colors are (r, g, b, a) tuples everywhere else so this covers them
+impl Animatable for (f32, f32, f32, f32) {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (
+            self.0.lerp(&to.0, t),
+            self.1.lerp(&to.1, t),
+            self.2.lerp(&to.2, t),
+            self.3.lerp(&to.3, t),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    BackOut,
+    ElasticOut,
+    BounceOut,
+    /// CSS style cubic bezier with control points (x1, y1, x2, y2)
+    CubicBezier(f32, f32, f32, f32),
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    /// Maps linear progress (0..1) onto the eased progress
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match *self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => if t < 0.5 {
+                2.0 * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+            },
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::ExpoIn => if t == 0.0 { 0.0 } else { 2f32.powf(10.0 * t - 10.0) },
+            Easing::ExpoOut => if t == 1.0 { 1.0 } else { 1.0 - 2f32.powf(-10.0 * t) },
+            Easing::BackOut => {
+                let c1 = 1.70158;
+                let c3 = c1 + 1.0;
+                1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2)
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 { return t }
+                let c4 = (2.0 * PI) / 3.0;
+                2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c4).sin() + 1.0
+            }
+            Easing::BounceOut => {
+                let n1 = 7.5625;
+                let d1 = 2.75;
+
+                if t < 1.0 / d1 {
+                    n1 * t * t
+                } else if t < 2.0 / d1 {
+                    let t = t - 1.5 / d1;
+                    n1 * t * t + 0.75
+                } else if t < 2.5 / d1 {
+                    let t = t - 2.25 / d1;
+                    n1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d1;
+                    n1 * t * t + 0.984375
+                }
+            }
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+            Easing::Custom(f) => f(t),
+        }
+    }
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, t: f32| {
+        3.0 * a * t * (1.0 - t).powi(2) + 3.0 * b * t * t * (1.0 - t) + t * t * t
+    };
+
+    //Solve for t on the x curve with bisection, plenty precise for animations
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut t = x;
+    for _ in 0..20 {
+        let current_x = bezier(x1, x2, t);
+        if (current_x - x).abs() < 1e-5 { break }
+
+        if current_x < x { low = t } else { high = t }
+        t = (low + high) / 2.0;
+    }
+
+    bezier(y1, y2, t)
+}
+
+/// Something which produces a value of `T` over time
+pub trait Animation<T: Animatable> {
+    /// Advances the animation by `dt` seconds and returns the current value
+    fn advance(&mut self, dt: f32) -> T;
+    fn is_finished(&self) -> bool;
+    /// Rewinds the animation back to its start
+    fn reset(&mut self);
+}
+
+/// Goes from one value to another over a fixed duration
+pub struct Tween<T> {
+    pub from: T,
+    pub to: T,
+    pub duration: Duration,
+    pub easing: Easing,
+    elapsed: f32,
+}
+
+impl<T: Animatable> Tween<T> {
+    pub fn new(from: T, to: T, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+impl<T: Animatable> Animation<T> for Tween<T> {
+    fn advance(&mut self, dt: f32) -> T {
+        self.elapsed += dt;
+
+        let duration = self.duration.as_secs_f32();
+        let progress = if duration > 0.0 { self.elapsed / duration } else { 1.0 };
+
+        self.from.lerp(&self.to, self.easing.apply(progress))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration.as_secs_f32()
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+/// Holds a value for a given duration, mostly useful inside of a `Sequence`
+pub struct Delay<T> {
+    pub value: T,
+    pub duration: Duration,
+    elapsed: f32,
+}
+
+impl<T: Animatable> Delay<T> {
+    pub fn new(value: T, duration: Duration) -> Self {
+        Self { value, duration, elapsed: 0.0 }
+    }
+}
+
+impl<T: Animatable> Animation<T> for Delay<T> {
+    fn advance(&mut self, dt: f32) -> T {
+        self.elapsed += dt;
+        self.value.clone()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration.as_secs_f32()
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+/// Interpolates between keyframes placed at points in time
+///
+/// The easing of a keyframe is used on the way *to* it
+pub struct Keyframes<T> {
+    frames: Vec<(f32, T, Easing)>,
+    elapsed: f32,
+}
+
+impl<T: Animatable> Keyframes<T> {
+    pub fn new(initial: T) -> Self {
+        Self {
+            frames: vec![(0.0, initial, Easing::Linear)],
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn key(mut self, at: Duration, value: T, easing: Easing) -> Self {
+        let at = at.as_secs_f32();
+        let index = self.frames.partition_point(|(time, _, _)| *time <= at);
+        self.frames.insert(index, (at, value, easing));
+        self
+    }
+
+    fn total_time(&self) -> f32 {
+        self.frames.last().map(|(time, _, _)| *time).unwrap_or(0.0)
+    }
+}
+
+impl<T: Animatable> Animation<T> for Keyframes<T> {
+    fn advance(&mut self, dt: f32) -> T {
+        self.elapsed = (self.elapsed + dt).min(self.total_time());
+
+        let next = self.frames
+            .iter()
+            .position(|(time, _, _)| *time > self.elapsed);
+
+        match next {
+            // Past the last keyframe
+            None => self.frames.last().unwrap().1.clone(),
+            Some(0) => self.frames[0].1.clone(),
+            Some(next) => {
+                let (start_time, start, _) = &self.frames[next - 1];
+                let (end_time, end, easing) = &self.frames[next];
+
+                let progress = (self.elapsed - start_time) / (end_time - start_time);
+                start.lerp(end, easing.apply(progress))
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.total_time()
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+/// Physically based animation towards a target
+///
+/// Simulates a damped spring going from 0 to 1 and lerps between `from` and `to` with it
+pub struct Spring<T> {
+    pub from: T,
+    pub to: T,
+    pub stiffness: f32,
+    pub damping: f32,
+    pub mass: f32,
+    position: f32,
+    velocity: f32,
+}
+
+impl<T: Animatable> Spring<T> {
+    pub fn new(from: T, to: T) -> Self {
+        Self {
+            from,
+            to,
+            stiffness: 170.0,
+            damping: 26.0,
+            mass: 1.0,
+            position: 0.0,
+            velocity: 0.0,
+        }
+    }
+
+    pub fn with_params(mut self, stiffness: f32, damping: f32, mass: f32) -> Self {
+        self.stiffness = stiffness;
+        self.damping = damping;
+        self.mass = mass;
+        self
+    }
+}
+
+impl<T: Animatable> Animation<T> for Spring<T> {
+    fn advance(&mut self, dt: f32) -> T {
+        //Fixed substeps so a long frame doesnt blow the simulation up
+        const STEP: f32 = 1.0 / 240.0;
+
+        let mut remaining = dt;
+        while remaining > 0.0 && !self.is_finished() {
+            let step = remaining.min(STEP);
+
+            let spring_force = -self.stiffness * (self.position - 1.0);
+            let damping_force = -self.damping * self.velocity;
+            let acceleration = (spring_force + damping_force) / self.mass;
+
+            self.velocity += acceleration * step;
+            self.position += self.velocity * step;
+
+            remaining -= step;
+        }
+
+        if self.is_finished() {
+            self.position = 1.0;
+            self.velocity = 0.0;
+        }
+
+        self.from.lerp(&self.to, self.position)
+    }
+
+    fn is_finished(&self) -> bool {
+        (self.position - 1.0).abs() < 0.001 && self.velocity.abs() < 0.001
+    }
+
+    fn reset(&mut self) {
+        self.position = 0.0;
+        self.velocity = 0.0;
+    }
+}
+
+/// Plays animations one after another, optionally repeating the whole thing.
+/// Starts with its first step so there's always a value to produce
+pub struct Sequence<T> {
+    steps: Vec<Box<dyn Animation<T>>>,
+    current: usize,
+    /// `None` repeats forever
+    repeat: Option<u32>,
+    repeats_done: u32,
+    /// Every step finished without any time passing, repeating that would never end
+    zero_length: bool,
+}
+
+impl<T: Animatable> Sequence<T> {
+    pub fn new(first: impl Animation<T> + 'static) -> Self {
+        Self {
+            steps: vec![Box::new(first)],
+            current: 0,
+            repeat: Some(0),
+            repeats_done: 0,
+            zero_length: false,
+        }
+    }
+
+    pub fn then(mut self, animation: impl Animation<T> + 'static) -> Self {
+        self.steps.push(Box::new(animation));
+        self
+    }
+
+    pub fn repeat(mut self, times: u32) -> Self {
+        self.repeat = Some(times);
+        self
+    }
+
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = None;
+        self
+    }
+}
+
+impl<T: Animatable> Animation<T> for Sequence<T> {
+    fn advance(&mut self, dt: f32) -> T {
+        let mut value = self.steps[self.current].advance(dt);
+        let mut restarts = 0;
+
+        while self.steps[self.current].is_finished() {
+            if self.current + 1 < self.steps.len() {
+                self.current += 1;
+            } else if self.repeat.is_none_or(|times| self.repeats_done < times) {
+                // Only the first step of a pass gets `dt`, so a second restart means the whole
+                // cycle finished without any time passing
+                restarts += 1;
+                if restarts > 1 {
+                    self.zero_length = true;
+                    break;
+                }
+
+                self.repeats_done += 1;
+                self.current = 0;
+                self.steps.iter_mut().for_each(|step| step.reset());
+            } else {
+                break;
+            }
+
+            value = self.steps[self.current].advance(0.0);
+        }
+
+        value
+    }
+
+    fn is_finished(&self) -> bool {
+        if self.zero_length { return true }
+
+        self.current + 1 >= self.steps.len()
+            && self.steps[self.current].is_finished()
+            && self.repeat.is_some_and(|times| self.repeats_done >= times)
+    }
+
+    fn reset(&mut self) {
+        self.current = 0;
+        self.repeats_done = 0;
+        self.zero_length = false;
+        self.steps.iter_mut().for_each(|step| step.reset());
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AnimationId(usize);
+
+/// Binds an animation to a property of a widget
+pub(crate) trait AnimationTrack<A, V, R> {
+    fn target(&self) -> UntypedHandle;
+    /// Advances the animation and writes the new value into the widget,
+    /// returns false if the widget doesnt exist anymore
+    fn apply(&mut self, ui_tree: &mut UiTree<A, V, R>, dt: f32) -> bool;
+    fn is_finished(&self) -> bool;
+}
+
+/// Writes an animated value into the widget
+type Setter<W, T> = Box<dyn FnMut(&mut W, T)>;
+
+struct Track<W, T> {
+    handle: TypedHandle<W>,
+    animation: Box<dyn Animation<T>>,
+    setter: Setter<W, T>,
+}
+
+impl<A, V, R, W, T> AnimationTrack<A, V, R> for Track<W, T>
+where
+    A: App<R>,
+    V: View<A, R>,
+    R: AnuraRenderer,
+    W: Widget<A, V, R>,
+    T: Animatable,
+{
+    fn target(&self) -> UntypedHandle {
+        self.handle.handle()
+    }
+
+    fn apply(&mut self, ui_tree: &mut UiTree<A, V, R>, dt: f32) -> bool {
+        match ui_tree.get_typed_mut(&self.handle) {
+            Some(widget) => {
+                let value = self.animation.advance(dt);
+                (self.setter)(widget, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.animation.is_finished()
+    }
+}
+
+/// Result of a single animation tick
+#[derive(Default)]
+pub(crate) struct AnimationTick {
+    pub animated: Vec<UntypedHandle>,
+    pub finished: Vec<(UntypedHandle, AnimationId)>,
+}
+
+pub struct AnimationManager<A, V, R> {
+    tracks: AHashMap<AnimationId, Box<dyn AnimationTrack<A, V, R>>>,
+    next_id: usize,
+    /// See `InputManager::now`
+    last_tick: Option<Duration>,
+}
+
+impl<A, V, R> Default for AnimationManager<A, V, R> {
+    fn default() -> Self {
+        Self {
+            tracks: AHashMap::new(),
+            next_id: 0,
+            last_tick: None,
+        }
+    }
+}
+
+impl<A, V, R> AnimationManager<A, V, R> {
+    /// Stops the animation leaving the property at its current value
+    pub fn stop(&mut self, id: AnimationId) {
+        self.tracks.remove(&id);
+    }
+
+    /// Stops every animation targeting the given widget
+    pub fn stop_all(&mut self, handle: &impl AsUntypedHandle) {
+        let handle = handle.handle();
+        self.tracks.retain(|_, track| track.target() != handle);
+    }
+
+    pub fn is_running(&self, id: AnimationId) -> bool {
+        self.tracks.contains_key(&id)
+    }
+}
+
+impl<A, V, R> AnimationManager<A, V, R>
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+{
+    pub fn add<W, T>(
+        &mut self,
+        handle: &TypedHandle<W>,
+        animation: impl Animation<T> + 'static,
+        setter: impl FnMut(&mut W, T) + 'static,
+    ) -> AnimationId
+    where
+        W: Widget<A, V, R>,
+        T: Animatable,
+    {
+        let id = AnimationId(self.next_id);
+        self.next_id += 1;
+
+        self.tracks.insert(id, Box::new(Track {
+            handle: handle.clone(),
+            animation: Box::new(animation),
+            setter: Box::new(setter),
+        }));
+
+        id
+    }
+
+    pub(crate) fn tick(&mut self, ui_tree: &mut UiTree<A, V, R>, now: Duration) -> AnimationTick {
+        let dt = match self.last_tick {
+            Some(last) => now.saturating_sub(last).as_secs_f32(),
+            None => 0.0,
+        };
+        self.last_tick = Some(now);
+
+        let mut tick = AnimationTick::default();
+
+        self.tracks.retain(|id, track| {
+            if !track.apply(ui_tree, dt) {
+                //Widget got removed, nobody to notify
+                return false;
+            }
+
+            let target = track.target();
+            if !tick.animated.contains(&target) {
+                tick.animated.push(target);
+            }
+
+            if track.is_finished() {
+                tick.finished.push((target, *id));
+                return false;
+            }
+
+            true
+        });
+
+        tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_length_sequence_finishes_instead_of_hanging() {
+        let mut sequence = Sequence::new(Tween::new(0.0, 1.0, Duration::ZERO))
+            .then(Delay::new(2.0, Duration::ZERO))
+            .repeat_forever();
+
+        assert_eq!(sequence.advance(0.1), 2.0);
+        assert!(sequence.is_finished());
+    }
+
+    #[test]
+    fn repeating_sequence_keeps_going() {
+        let mut sequence = Sequence::new(Tween::new(0.0, 1.0, Duration::from_secs(1)))
+            .repeat(1);
+
+        assert_eq!(sequence.advance(0.5), 0.5);
+        sequence.advance(0.5);
+        assert!(!sequence.is_finished());
+        sequence.advance(1.0);
+        assert!(sequence.is_finished());
+    }
+}
@@ -3,26 +3,58 @@ use std::rc::Rc;
 use ahash::AHashMap;
 use graphics::WgpuRenderer;
 use winit::{
-    dpi::PhysicalSize,
-    event::{Event, KeyboardInput, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{Event, Ime, WindowEvent},
     event_loop::ControlFlow,
     window::Window,
 };
 
 use crate::{
-    error::AnuraError,
-    event::{AppEvent},
+    error::{AnuraError, ErrorAction, ErrorOrigin},
+    event::{AppEvent, AnuraUserEvent, ViewEvent, WidgetEvent},
+    action::{ActionMap, ActionScope},
+    replay::{InputEvent, InputRecorder, InputRecording, InputReplayer},
+    drag::FileDrop,
     graphics::{self, AnuraRenderer},
     input::InputManager,
-    view::{View, ViewWrapper, ViewWrapperTrait}, font::FontManager, arena::Arena, handle::UntypedHandle,
+    clipboard::{self, Clipboard},
+    view::{View, ViewWrapper, ViewWrapperTrait}, font::FontManager, arena::Arena, handle::{AsUntypedHandle, UntypedHandle},
+    task::TaskManager, widget::Layout,
 };
 
+/// The app itself lives inside of `AnuraApp` so that its always reachable
+/// (through `ctx.app_state()`), which is why these dont take `self`
 pub trait App<R: AnuraRenderer>: Sized {
     fn handle_event(
-        &mut self, 
-        _ctx: &mut AnuraApp<'_, Self, R>, 
+        _ctx: &mut AnuraApp<'_, Self, R>,
         _event: AppEvent
     ) -> Result<(), AnuraError<R::ErrorMessage>>;
+
+    /// Called with every error returned by the app, views and widgets during a frame
+    ///
+    /// By default errors are only logged through the `log` crate, so nothing shows up
+    /// unless the app sets up a logger
+    fn handle_error(
+        _ctx: &mut AnuraApp<'_, Self, R>,
+        error: AnuraError<R::ErrorMessage>,
+        origin: ErrorOrigin,
+    ) -> ErrorAction {
+        log::error!("[{origin:?}] {error}");
+        ErrorAction::Ignore
+    }
+
+    /// Called when the window is closed or an exit was requested,
+    /// returning false keeps the app running (e.g. to ask about unsaved changes first)
+    fn should_exit(_ctx: &mut AnuraApp<'_, Self, R>) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PendingExit {
+    /// Can still be vetoed by `App::should_exit`
+    Requested,
+    Forced,
 }
 
 #[derive(Default)]
@@ -37,15 +69,39 @@ enum CurrentView {
 }
 
 pub struct AnuraApp<'a, A, R: AnuraRenderer> {
-    pub app: Option<A>,
+    app_state: A,
     views: Arena<Box<dyn ViewWrapperTrait<A, R> + 'a>>,
     current_view: CurrentView,
     view_history: Vec<usize>,
     
     pub renderer: R,
     pub(crate) render_cache: AHashMap<UntypedHandle, Vec<R::RenderCommand>>,
+    /// (font, page) pairs of the glyph atlas pages the cached commands of a widget draw from
+    pub(crate) render_cache_pages: AHashMap<UntypedHandle, Vec<(usize, usize)>>,
+    /// Where every widget was put the last time it was rendered
+    pub(crate) layout_cache: AHashMap<UntypedHandle, Layout>,
     pub font_manager: FontManager<R>,
     pub input_manager: InputManager,
+    pub task_manager: TaskManager,
+    /// Used by text widgets for copy and paste
+    pub clipboard: Box<dyn Clipboard>,
+    /// Shortcuts, checked every update before widgets get to see the key presses
+    pub actions: ActionMap,
+
+    errors: Vec<(ErrorOrigin, AnuraError<R::ErrorMessage>)>,
+    /// Ime events since the last update, they go to the focused widget
+    pending_ime: Vec<Ime>,
+    /// Where the focused widget wants the ime this frame, see `request_ime`
+    ime_request: Option<(f32, f32)>,
+    ime_allowed: bool,
+    ime_position: Option<(f32, f32)>,
+    /// Files dragged onto the window since the last update
+    pending_files: Vec<FileDrop>,
+    recorder: Option<InputRecorder>,
+    /// Real input is ignored while a recording is played back
+    replayer: Option<InputReplayer>,
+    pending_exit: Option<PendingExit>,
+    minimized: bool,
 
     window: Rc<Window>,
 }
@@ -70,14 +126,29 @@ where
     pub async fn with_renderer(app: A, window: Rc<Window>, renderer: R) -> AnuraApp<'a, A, R> {
 
         let mut this = Self {
-            app: Some(app),
+            app_state: app,
             views: Arena::new(),
             current_view: CurrentView::None,
             view_history: Vec::new(),
             renderer,
             render_cache: AHashMap::new(),
+            render_cache_pages: AHashMap::new(),
+            layout_cache: AHashMap::new(),
             font_manager: FontManager::new(),
             input_manager: InputManager::default(),
+            task_manager: TaskManager::default(),
+            clipboard: clipboard::default_clipboard(),
+            actions: ActionMap::new(),
+            errors: Vec::new(),
+            pending_ime: Vec::new(),
+            ime_request: None,
+            ime_allowed: false,
+            ime_position: None,
+            pending_files: Vec::new(),
+            recorder: None,
+            replayer: None,
+            pending_exit: None,
+            minimized: false,
             window,
         };
 
@@ -86,12 +157,7 @@ where
     }
 
     fn init(&mut self) {
-        let mut app = self.app.take().unwrap();
-
-        app.handle_event(self, AppEvent::Init);
-
-        self.app = Some(app);
-
+        //Camera first so the app can already render things during init
         self.renderer.setup_camera(
             [0.0, 0.0, 1.0],
             [0.0, 0.0, 0.0],
@@ -99,6 +165,133 @@ where
             -10.0,
             10.0,
         );
+
+        self.dispatch_app_event(AppEvent::Init);
+        self.handle_errors();
+    }
+
+    pub fn app_state(&mut self) -> &mut A {
+        &mut self.app_state
+    }
+
+    /// Replaces the clipboard, e.g. with a `MemoryClipboard` to keep copied text inside the app
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.clipboard = Box::new(clipboard);
+    }
+
+    fn dispatch_app_event(&mut self, event: AppEvent) {
+        if let Err(err) = A::handle_event(self, event) {
+            self.report_error(ErrorOrigin::App, err);
+        }
+    }
+
+    /// Asks the app to exit at the end of the current frame, `App::should_exit` can still veto it
+    pub fn request_exit(&mut self) {
+        self.pending_exit.get_or_insert(PendingExit::Requested);
+    }
+
+    /// Exits at the end of the current frame without asking `App::should_exit`
+    pub fn force_exit(&mut self) {
+        self.pending_exit = Some(PendingExit::Forced);
+    }
+
+    /// Keeps the ime enabled for this frame and puts its candidate window at `position`
+    /// (in physical pixels, usually right below the caret)
+    ///
+    /// Widgets taking text input call this every frame while they're focused,
+    /// the ime gets disabled again in frames nobody asks for it
+    pub fn request_ime(&mut self, position: (f32, f32)) {
+        self.ime_request = Some(position);
+    }
+
+    /// Enables or disables the ime depending on whether a widget asked for it this frame
+    fn update_ime(&mut self) {
+        let request = self.ime_request.take();
+
+        if request.is_some() != self.ime_allowed {
+            self.ime_allowed = request.is_some();
+            self.window.set_ime_allowed(self.ime_allowed);
+
+            if !self.ime_allowed {
+                self.ime_position = None;
+            }
+        }
+
+        if let Some(position) = request {
+            if self.ime_position != Some(position) {
+                self.ime_position = Some(position);
+                self.window.set_ime_position(PhysicalPosition::new(position.0, position.1));
+            }
+        }
+    }
+
+    /// Starts recording every input event, replaces a recording which is already running
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(InputRecorder::new(self.window.inner_size().into(), self.input_manager.now()));
+    }
+
+    /// Stops recording, None if nothing was being recorded
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(InputRecorder::finish)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Plays a recording back from the next frame on, ignoring real input until it's done.
+    /// `AppEvent::ReplayFinished` is sent at the end
+    ///
+    /// The window gets the size it had while recording and held keys/buttons are let go,
+    /// everything else (open views, focus, text) should match the start of the recording
+    pub fn replay(&mut self, recording: InputRecording) {
+        let (width, height) = recording.window_size;
+        if width > 0 && height > 0 && self.window.inner_size() != PhysicalSize::new(width, height) {
+            self.window.set_inner_size(PhysicalSize::new(width, height));
+        }
+
+        self.input_manager.reset();
+        self.replayer = Some(InputReplayer::new(recording));
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replayer.is_some()
+    }
+
+    /// Passes input to the input manager, or the focused widget for ime events
+    fn handle_input(
+        input_manager: &mut InputManager,
+        recorder: &mut Option<InputRecorder>,
+        pending_ime: &mut Vec<Ime>,
+        event: &InputEvent,
+    ) {
+        if let Some(recorder) = recorder {
+            recorder.record(event.clone(), input_manager.now());
+        }
+
+        match event {
+            InputEvent::Ime(ime) => pending_ime.push(ime.clone()),
+            event => event.apply(input_manager),
+        }
+    }
+
+    /// Feeds the recorded input of this frame in place of the real one
+    fn replay_frame(&mut self) {
+        let replayer = match &mut self.replayer {
+            Some(replayer) => replayer,
+            None => return,
+        };
+
+        if replayer.is_finished() {
+            self.replayer = None;
+            self.dispatch_app_event(AppEvent::ReplayFinished);
+            return;
+        }
+
+        let (recorder, pending_ime) = (&mut self.recorder, &mut self.pending_ime);
+        replayer.play_frame(&mut self.input_manager, |input_manager, event| {
+            Self::handle_input(input_manager, recorder, pending_ime, event);
+        });
     }
 
     /// Pushes a new view onto the view stack
@@ -106,13 +299,21 @@ where
     where
         V: View<A, R> + 'static,
     {
-        
+        //Make the view current before its init so anything it spawns there knows its id.
+        //The slot is taken right away so views pushed during the init get their own
+        let id = self.views.reserve();
+        self.view_history.push(id);
+        self.current_view = CurrentView::View(id);
+
         let wrapped_view = ViewWrapper::new(view, self);
         let boxed = Box::new(wrapped_view);
 
-        let id = self.views.insert(boxed);
-        self.view_history.push(id);
-        self.current_view = CurrentView::View(id);
+        self.views.overwrite(id, boxed);
+    }
+
+    /// Origin and size a widget got the last time it was rendered
+    pub fn widget_layout(&self, handle: &impl AsUntypedHandle) -> Option<Layout> {
+        self.layout_cache.get(&handle.handle()).copied()
     }
 
     pub fn get_current_view_id(&self) -> usize {
@@ -136,7 +337,9 @@ where
                     None => CurrentView::None,
                     Some(last_view) => CurrentView::View(*last_view)
                 };
-                
+
+                self.task_manager.cancel_view(id);
+
                 self.views.remove(id).unwrap()
             },
         }
@@ -164,10 +367,113 @@ where
 
     /// Updates the current view
     pub fn update(&mut self) {
-        self.run_on_moved_out_view(self.get_current_view_id(), |mut app, view| {
-            view.update(&mut app);
+        let view_id = self.get_current_view_id();
+
+        self.input_manager.update_gestures();
+
+        self.dispatch_app_event(AppEvent::Update);
+
+        //Pages cached commands draw from are kept, the commands are useless once an atlas got cleared though
+        let render_cache = &self.render_cache;
+        self.render_cache_pages.retain(|handle, _| render_cache.contains_key(handle));
+        let referenced = self.render_cache_pages.values().flatten().copied().collect::<Vec<_>>();
+
+        if self.font_manager.next_frame(&mut self.renderer, &referenced) {
+            self.render_cache.clear();
+        }
+
+        self.task_manager.poll();
+        let finished_tasks = self.task_manager.take_finished(view_id);
+
+        let ime_events = std::mem::take(&mut self.pending_ime);
+        let file_drops = std::mem::take(&mut self.pending_files);
+
+        let has_focus = self.views.get_mut(view_id).is_some_and(|view| view.has_focus());
+        let now = self.input_manager.now();
+        let actions = self.actions.process(
+            &mut self.input_manager.key_presses,
+            self.input_manager.modifiers,
+            has_focus,
+            now,
+        );
+
+        self.run_on_moved_out_view(view_id, |app, view| {
+            for task in finished_tasks {
+                if let Err(err) = view.deliver_task(app, task) {
+                    app.report_error(ErrorOrigin::View(view_id), err);
+                }
+            }
+
+            for ime in ime_events {
+                let event = match ime {
+                    Ime::Preedit(text, cursor) => WidgetEvent::ImePreedit { text, cursor },
+                    Ime::Commit(text) => WidgetEvent::ImeCommit(text),
+                    // Whatever was being composed is gone
+                    Ime::Disabled => WidgetEvent::ImePreedit { text: String::new(), cursor: None },
+                    Ime::Enabled => continue,
+                };
+
+                view.dispatch_focused(app, event);
+            }
+
+            for (scope, action) in actions {
+                match scope {
+                    ActionScope::App => app.dispatch_app_event(AppEvent::Action(action)),
+                    ActionScope::View => {
+                        if let Err(err) = view.dispatch_view_event(app, ViewEvent::Action(action)) {
+                            app.report_error(ErrorOrigin::View(view_id), err);
+                        }
+                    }
+                    ActionScope::Focused => view.dispatch_focused(app, WidgetEvent::Action(action)),
+                }
+            }
+
+            if !file_drops.is_empty() {
+                view.receive_files(file_drops);
+            }
+
+            if let Err(err) = view.update(app) {
+                app.report_error(ErrorOrigin::View(view_id), err);
+            }
         });
 
+        self.update_ime();
+
+        for err in self.clipboard.take_errors() {
+            self.report_error(ErrorOrigin::App, AnuraError::Clipboard(err));
+        }
+
+        for err in self.font_manager.take_errors() {
+            self.report_error(ErrorOrigin::Render, err);
+        }
+
+        self.handle_errors();
+    }
+
+    /// Queues up an error to be passed to `App::handle_error` at the end of the frame
+    pub fn report_error(&mut self, origin: ErrorOrigin, error: AnuraError<R::ErrorMessage>) {
+        self.errors.push((origin, error));
+    }
+
+    /// Passes all reported errors to the app's error hook and carries out what it decided
+    fn handle_errors(&mut self) {
+        if self.errors.is_empty() { return }
+
+        let errors = std::mem::take(&mut self.errors);
+
+        for (origin, error) in errors {
+            match A::handle_error(self, error, origin) {
+                ErrorAction::Ignore => {}
+                ErrorAction::DisableWidget => {
+                    if let ErrorOrigin::Widget { view, handle } = origin {
+                        if let Some(view) = self.views.get_mut(view) {
+                            view.disable_widget(handle);
+                        }
+                    }
+                }
+                ErrorAction::Exit => self.force_exit(),
+            }
+        }
     }
 
     pub fn render(&mut self) -> Result<(), AnuraError<R::ErrorMessage>> {
@@ -184,6 +490,14 @@ where
 
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         let (new_width, new_height) = new_size.into();
+
+        //Winit doesnt have a minimize event but the window gets resized to 0x0
+        let minimized = new_width == 0 || new_height == 0;
+        if minimized != self.minimized {
+            self.minimized = minimized;
+            self.dispatch_app_event(if minimized { AppEvent::Minimized } else { AppEvent::Restored });
+        }
+
         if new_width > 0 && new_height > 0 {
             self.renderer.on_window_resize((new_width, new_height));
             //Clear render command cache in order to avoid stretching
@@ -194,7 +508,7 @@ where
     /// Main loop of the app
     ///
     /// Handles winit events
-    pub fn handle_events(&mut self, event: Event<()>, control_flow: &mut ControlFlow) {
+    pub fn handle_events(&mut self, event: Event<AnuraUserEvent>, control_flow: &mut ControlFlow) {
         match event {
             Event::WindowEvent {
                 ref event,
@@ -202,58 +516,72 @@ where
             } if window_id == self.window.id() => self.handle_window_events(event, control_flow),
             Event::RedrawRequested(_) => match self.render() {
                 Ok(_) => {}
-                Err(AnuraError::FatalError { msg }) => {
-                    //TODO: handle fatal errors differently (maybe just panic?)
+                Err(AnuraError::SurfaceLost { .. }) => {
                     self.resize(self.window.inner_size());
-                    // *control_flow = ControlFlow::Exit;
-                    // eprintln!("{msg:?}")
                 }
+                Err(err) => self.report_error(ErrorOrigin::Render, err),
             },
+            Event::UserEvent(AnuraUserEvent::TaskFinished) => self.task_manager.poll(),
+            Event::Suspended => self.dispatch_app_event(AppEvent::Suspended),
+            Event::Resumed => self.dispatch_app_event(AppEvent::Resumed),
             Event::MainEventsCleared => {
+                self.replay_frame();
+                if self.replayer.is_none() {
+                    self.input_manager.tick();
+                }
+
                 self.update();
 
                 self.window.request_redraw();
 
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.next_frame(self.input_manager.now());
+                }
                 self.input_manager.update_inputs();
             }
             _ => {}
         }
+
+        //Errors from events outside of update (rendering, app events etc.)
+        self.handle_errors();
+
+        if let Some(pending_exit) = self.pending_exit.take() {
+            if pending_exit == PendingExit::Forced || A::should_exit(self) {
+                self.exit(control_flow);
+            }
+        }
     }
 
     /// Handles just the windowevent part of winit
-    pub fn handle_window_events(&mut self, event: &WindowEvent, control_flow: &mut ControlFlow) {
-        match event {
-            WindowEvent::KeyboardInput {
-                input:
-                    KeyboardInput {
-                        state,
-                        virtual_keycode: Some(keycode),
-                        ..
-                    },
-                ..
-            } => self.input_manager.update_key(keycode, state),
-            WindowEvent::ReceivedCharacter(character) => {
-                self.input_manager.last_received_char = Some(*character)
+    pub fn handle_window_events(&mut self, event: &WindowEvent, _control_flow: &mut ControlFlow) {
+        if let Some(input) = InputEvent::from_window_event(event) {
+            if self.replayer.is_none() {
+                self.input_manager.tick();
+                Self::handle_input(&mut self.input_manager, &mut self.recorder, &mut self.pending_ime, &input);
             }
-            WindowEvent::CloseRequested => self.exit(control_flow),
+            return;
+        }
+
+        match event {
+            WindowEvent::CloseRequested => self.request_exit(),
+            WindowEvent::Focused(focused) => self.dispatch_app_event(AppEvent::Focused(*focused)),
             WindowEvent::Resized(physical_size) => self.resize(*physical_size),
             WindowEvent::ScaleFactorChanged { new_inner_size, .. } => self.resize(**new_inner_size),
-            WindowEvent::CursorMoved { position, .. } => {
-                self.input_manager.update_mouse_pos(position)
-            }
-            WindowEvent::MouseInput { state, button, .. } => {
-                self.input_manager.update_mouse_button(state, button)
-            }
-            WindowEvent::MouseWheel { delta: _, phase: _, .. } => {},
+            WindowEvent::HoveredFile(path) => self.pending_files.push(FileDrop::Hovered(path.clone())),
+            WindowEvent::DroppedFile(path) => self.pending_files.push(FileDrop::Dropped(path.clone())),
+            WindowEvent::HoveredFileCancelled => self.pending_files.push(FileDrop::Cancelled),
             _ => {}
         }
     }
 
     fn exit(&mut self, control_flow: &mut ControlFlow) {
-        let mut app = self.app.take().unwrap();
+        self.dispatch_app_event(AppEvent::Exit);
 
-        app.handle_event(self, AppEvent::Exit);
-        
+        //Errors while exiting can only be reported, theres no stopping it now
+        let errors = std::mem::take(&mut self.errors);
+        for (origin, error) in errors {
+            A::handle_error(self, error, origin);
+        }
 
         *control_flow = ControlFlow::Exit;
     }
@@ -33,6 +33,19 @@ impl<T> Arena<T> {
         }
     }
 
+    /// Takes a slot without filling it yet, it stays empty until `overwrite` puts a value there
+    #[inline]
+    #[must_use]
+    pub fn reserve(&mut self) -> usize {
+        match self.free_spaces.pop() {
+            Some(idx) => idx,
+            None => {
+                self.vec.push(None);
+                self.vec.len() - 1
+            }
+        }
+    }
+
     #[inline]
     pub fn remove(&mut self, idx: usize) -> Option<T> {
         self.free_spaces.push(idx);
@@ -56,4 +69,22 @@ impl<T> Arena<T> {
             None => None,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_slot_isnt_handed_out_again() {
+        let mut arena = Arena::new();
+        let reserved = arena.reserve();
+        let inserted = arena.insert("nested");
+
+        assert_ne!(reserved, inserted);
+        assert_eq!(arena.get(reserved), None);
+
+        arena.overwrite(reserved, "outer");
+        assert_eq!(arena.get(reserved), Some(&"outer"));
+        assert_eq!(arena.get(inserted), Some(&"nested"));
+    }
+}
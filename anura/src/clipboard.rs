@@ -0,0 +1,105 @@
+use crate::error::BoxedError;
+
+/// Where copied text goes, swap it out with `AnuraApp::set_clipboard`
+pub trait Clipboard {
+    /// Current text on the clipboard, None if it's empty or doesn't hold text
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: &str);
+
+    /// Errors since the last call, the app reports them as `AnuraError::Clipboard` every frame
+    fn take_errors(&mut self) -> Vec<BoxedError> {
+        Vec::new()
+    }
+}
+
+/// Keeps the text to itself, nothing outside of the app can see it
+///
+/// Used when there is no system clipboard and handy for tests
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.text = Some(text.to_owned());
+    }
+}
+
+/// The clipboard of the OS, falls back to a `MemoryClipboard` if it can't be opened
+#[cfg(feature = "system-clipboard")]
+pub struct SystemClipboard {
+    // Kept open for the whole app, on x11 the copied text is gone once this is dropped
+    clipboard: Option<arboard::Clipboard>,
+    fallback: MemoryClipboard,
+    errors: Vec<BoxedError>,
+}
+
+#[cfg(feature = "system-clipboard")]
+impl SystemClipboard {
+    pub fn new() -> Self {
+        let mut errors = Vec::new();
+
+        // Falling back is reported as an error too, copied text won't leave the app
+        let clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => Some(clipboard),
+            Err(err) => {
+                errors.push(err.into());
+                None
+            }
+        };
+
+        Self { clipboard, fallback: MemoryClipboard::new(), errors }
+    }
+}
+
+#[cfg(feature = "system-clipboard")]
+impl Default for SystemClipboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "system-clipboard")]
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        match &mut self.clipboard {
+            Some(clipboard) => clipboard.get_text().ok(),
+            None => self.fallback.get_text(),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) {
+        match &mut self.clipboard {
+            Some(clipboard) => {
+                if let Err(err) = clipboard.set_text(text) {
+                    self.errors.push(err.into());
+                }
+            }
+            None => self.fallback.set_text(text),
+        }
+    }
+
+    fn take_errors(&mut self) -> Vec<BoxedError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+/// The system clipboard if the `system-clipboard` feature is on, otherwise an in-memory one
+pub(crate) fn default_clipboard() -> Box<dyn Clipboard> {
+    #[cfg(feature = "system-clipboard")]
+    return Box::new(SystemClipboard::new());
+
+    #[cfg(not(feature = "system-clipboard"))]
+    return Box::new(MemoryClipboard::new());
+}
@@ -1,6 +1,8 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, future::Future};
 
-use crate::{uitree::UiTree, graphics::AnuraRenderer, app::{AnuraApp, App}, view::View, handle::{AsUntypedHandle, NodeType}, widget::Layout, event::WidgetEvent, error::AnuraError};
+use winit::event::MouseButton;
+
+use crate::{drag::{ActiveDrag, DragPayload, DragPreview, DRAG_THRESHOLD}, uitree::UiTree, graphics::AnuraRenderer, app::{AnuraApp, App}, view::View, handle::{AsUntypedHandle, NodeType, TypedHandle}, widget::{Layout, Widget}, event::WidgetEvent, error::AnuraError, animation::{Animation, Animatable, AnimationId}, task::{TaskId, TaskOwner, CancellationToken}};
 
 pub struct AnuraContext<'a, 'b, A, V, R>
 where
@@ -17,11 +19,138 @@ where
     V: View<A, R> + 'static,
     R: AnuraRenderer + 'static,
 {
+    pub fn app_state(&mut self) -> &mut A {
+        self.app.app_state()
+    }
+
+    /// Where the current widget was put the last time it was rendered,
+    /// `None` before its first render
+    pub fn layout(&self) -> Option<Layout> {
+        match self.current {
+            NodeType::Handle(handle) => self.app.widget_layout(&handle),
+            NodeType::Root => None,
+        }
+    }
+
+    /// Gives the current widget keyboard focus
+    pub fn focus(&mut self) {
+        if let NodeType::Handle(handle) = self.current {
+            self.ui_tree.focus(&handle);
+        }
+    }
+
+    pub fn is_focused(&self) -> bool {
+        match self.current {
+            NodeType::Handle(handle) => self.ui_tree.is_focused(&handle),
+            NodeType::Root => false,
+        }
+    }
+
+    /// Makes the current widget draggable, called every update. Once the mouse moves far
+    /// enough with the left button down on the widget, a drag carrying `payload()` starts
+    ///
+    /// Returns true in the frame the drag started
+    pub fn drag_source<T: 'static>(&mut self, payload: impl FnOnce() -> T) -> bool {
+        let (handle, layout) = match (self.current, self.layout()) {
+            (NodeType::Handle(handle), Some(layout)) => (handle, layout),
+            _ => return false,
+        };
+
+        let input = &self.app.input_manager;
+        let drag = &mut self.ui_tree.drag;
+        let mouse = (input.mouse_position.x as f32, input.mouse_position.y as f32);
+
+        let (x, y, _) = layout.origin;
+        let (width, height) = layout.available_space;
+        let hovered = mouse.0 >= x && mouse.0 <= x + width && mouse.1 >= y && mouse.1 <= y + height;
+
+        if input.just_pressed(MouseButton::Left) && hovered && drag.active.is_none() {
+            drag.pressed = Some(handle);
+        }
+
+        if drag.pressed != Some(handle) { return false }
+
+        let distance = match input.drag_distance(MouseButton::Left) {
+            Some(distance) if input.is_down(MouseButton::Left) => distance,
+            _ => {
+                drag.pressed = None;
+                return false;
+            }
+        };
+
+        if distance.0.hypot(distance.1) < DRAG_THRESHOLD { return false }
+
+        // Measured from where the mouse went down, not where it is now
+        let grab_offset = (mouse.0 - distance.0 - x, mouse.1 - distance.1 - y);
+
+        drag.pressed = None;
+        drag.active = Some(ActiveDrag {
+            source: Some(handle),
+            payload: DragPayload::new(payload()),
+            preview: DragPreview::default(),
+            grab_offset,
+            hovered: None,
+        });
+
+        true
+    }
+
+    /// Lets `T` payloads be dropped on the current widget, can be called for multiple types
+    pub fn accept_drops<T: 'static>(&mut self) {
+        if let NodeType::Handle(handle) = self.current {
+            self.ui_tree.drag.accept::<T>(handle);
+        }
+    }
+
+    /// Lets any payload be dropped on the current widget
+    pub fn accept_any_drop(&mut self) {
+        if let NodeType::Handle(handle) = self.current {
+            self.ui_tree.drag.accept_any(handle);
+        }
+    }
+
+    /// Sends touch gestures starting on the current widget to it as `WidgetEvent::Gesture`
+    pub fn accept_gestures(&mut self) {
+        if let NodeType::Handle(handle) = self.current {
+            self.ui_tree.gestures.targets.insert(handle);
+        }
+    }
+
+    /// True while the current widget is being dragged
+    pub fn is_dragging(&self) -> bool {
+        match (self.current, &self.ui_tree.drag.active) {
+            (NodeType::Handle(handle), Some(drag)) => drag.source == Some(handle),
+            _ => false,
+        }
+    }
+
+    /// Payload of the drag in progress, if there is one
+    pub fn drag_payload(&self) -> Option<&DragPayload> {
+        self.ui_tree.drag.active.as_ref().map(|drag| &drag.payload)
+    }
+
+    /// Changes what follows the mouse for the drag in progress
+    pub fn set_drag_preview(&mut self, preview: DragPreview) {
+        if let Some(drag) = &mut self.ui_tree.drag.active {
+            drag.preview = preview;
+        }
+    }
+
+    /// Stops the drag in progress as if it was let go somewhere nobody takes it
+    pub fn cancel_drag(&mut self) {
+        self.ui_tree.drag.cancel_requested = true;
+    }
+
+    /// Sends `ViewEvent::Action` to the view once every widget is done updating
+    pub fn send_action(&mut self, action: impl Into<String>) {
+        self.ui_tree.pending_actions.push(action.into());
+    }
+
     pub fn issue_rerender(&mut self) {
         if let NodeType::Handle(handle) = &self.current {
 
             let mut handles = VecDeque::new();
-            handles.push_back(handle.clone());
+            handles.push_back(*handle);
             loop {
                 let curr_handle = match handles.pop_front() {
                     Some(handle) => handle,
@@ -32,10 +161,78 @@ where
                 self.app.render_cache.remove(&curr_handle);
             }
 
-        } else { 
+            //Parents cache their children's commands as well so they have to go too
+            let mut parent = self.ui_tree.parent_arena.vec[handle.index];
+            while let Some(NodeType::Handle(parent_handle)) = parent {
+                self.app.render_cache.remove(&parent_handle);
+                parent = self.ui_tree.parent_arena.vec[parent_handle.index];
+            }
+
+        } else {
             panic!("Cannot rerender a non widget")
         }
     }
+
+    /// Starts animating a property of a widget, `setter` writes the animated value into it
+    ///
+    /// The widget gets rerendered every frame while the animation runs and receives
+    /// `WidgetEvent::AnimationFinished` once its done
+    pub fn animate<W, T>(
+        &mut self,
+        handle: &TypedHandle<W>,
+        animation: impl Animation<T> + 'static,
+        setter: impl FnMut(&mut W, T) + 'static,
+    ) -> AnimationId
+    where
+        W: Widget<A, V, R>,
+        T: Animatable,
+    {
+        self.ui_tree.animations.add(handle, animation, setter)
+    }
+
+    pub fn stop_animation(&mut self, id: AnimationId) {
+        self.ui_tree.animations.stop(id);
+    }
+
+    /// Runs `f` on a background thread, its return value comes back to the current
+    /// view/widget as a `TaskFinished` event on the main thread
+    ///
+    /// The task is cancelled once the view gets popped or the widget removed
+    pub fn spawn_task<T, F>(&mut self, f: F) -> TaskId
+    where
+        T: Send + 'static,
+        F: FnOnce(CancellationToken) -> T + Send + 'static,
+    {
+        let owner = self.task_owner();
+        self.app.task_manager.spawn_thread(owner, f)
+    }
+
+    /// Same as `spawn_task` but drives a future to completion instead
+    pub fn spawn_future<T, F>(&mut self, future: F) -> TaskId
+    where
+        T: Send + 'static,
+        F: Future<Output = T> + Send + 'static,
+    {
+        let owner = self.task_owner();
+        self.app.task_manager.spawn_future(owner, future)
+    }
+
+    pub fn cancel_task(&mut self, id: TaskId) {
+        self.app.task_manager.cancel(id);
+    }
+
+    fn task_owner(&self) -> TaskOwner {
+        let generation = match self.current {
+            NodeType::Handle(handle) => self.ui_tree.generation(&handle),
+            NodeType::Root => 0,
+        };
+
+        TaskOwner {
+            view: self.app.get_current_view_id(),
+            node: self.current,
+            generation,
+        }
+    }
 }
 
 pub struct RenderContext<'a, 'b, A, V, R>
@@ -45,6 +242,9 @@ where
     pub app: &'b mut AnuraApp<'a, A, R>,
     pub ui_tree: &'b UiTree<A, V, R>,
     pub current: NodeType,
+    /// True while drawing the drag preview, which isn't where the widget actually is
+    /// so nothing of it may end up in the caches
+    pub(crate) preview: bool,
 }
 
 
@@ -54,7 +254,14 @@ where
     V: View<A, R> + 'static,
     R: AnuraRenderer + 'static,
 {
-    
+    /// True if the current widget got disabled with `UiTree::disable`
+    pub fn is_disabled(&self) -> bool {
+        match self.current {
+            NodeType::Handle(handle) => self.ui_tree.is_disabled(&handle),
+            NodeType::Root => false,
+        }
+    }
+
     pub fn render(
         &mut self, 
         handle: &impl AsUntypedHandle, 
@@ -69,16 +276,50 @@ where
             } 
         }
 
+        //Nothing to draw until the widget got initialized
+        if self.ui_tree.pending_init.contains(&handle) {
+            return Ok(Vec::new());
+        }
+
+        if self.preview {
+            let widget = self.ui_tree.get_untyped_ref(&handle)
+                .ok_or(AnuraError::InvalidHandle { handle })?;
+            let mut ctx = RenderContext {
+                app: self.app,
+                ui_tree: self.ui_tree,
+                current: NodeType::Handle(handle),
+                preview: true,
+            };
+
+            return widget.generate_mesh(&mut ctx, view, layout);
+        }
+
+        self.app.layout_cache.insert(handle, layout);
+
         if self.app.render_cache.get(&handle).is_none() {
-            let widget = self.ui_tree.get_untyped_ref(&handle).unwrap();
+            let widget = self.ui_tree.get_untyped_ref(&handle)
+                .ok_or(AnuraError::InvalidHandle { handle })?;
+
+            // Atlas pages the commands draw from, they can't be evicted while the commands are cached.
+            // Pages touched by the widget count for the parent being rendered as well
+            let outer = self.app.font_manager.take_touched();
+
             let mut ctx = RenderContext { 
                 app: self.app,
                 ui_tree: self.ui_tree,
-                current: NodeType::Handle(handle)
+                current: NodeType::Handle(handle),
+                preview: false,
             };
 
-            let commands = widget.generate_mesh(&mut ctx, view, layout)?;
-            self.app.render_cache.insert(handle.clone(), commands);
+            let commands = widget.generate_mesh(&mut ctx, view, layout);
+            let pages = self.app.font_manager.take_touched();
+            self.app.font_manager.touch(&outer);
+            self.app.font_manager.touch(&pages);
+
+            self.app.render_cache.insert(handle, commands?);
+            self.app.render_cache_pages.insert(handle, pages);
+        } else if let Some(pages) = self.app.render_cache_pages.get(&handle) {
+            self.app.font_manager.touch(pages);
         }
 
         Ok(self.app.render_cache.get(&handle.handle())
@@ -0,0 +1,219 @@
+use std::{
+    any::{Any, TypeId},
+    fmt,
+    path::PathBuf,
+    rc::Rc,
+};
+
+use ahash::AHashMap;
+
+use crate::{handle::UntypedHandle, widget::{widget_at, Layout}};
+
+/// File extensions `DroppedFiles::images` picks out, the formats textures can be loaded from
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "tif", "tiff", "webp", "tga", "dds", "hdr", "exr", "pnm", "pbm", "pgm", "ppm", "ff",
+];
+
+/// How far (in pixels) the mouse has to move with the button down before a drag starts
+pub const DRAG_THRESHOLD: f32 = 5.0;
+/// Depth the drag preview is drawn at, in front of the rest of the ui
+pub(crate) const PREVIEW_Z: f32 = 5.0;
+
+/// Whatever is being dragged, cheap to clone so every event can carry it
+#[derive(Clone)]
+pub struct DragPayload(Rc<dyn Any>);
+
+impl DragPayload {
+    pub fn new<T: 'static>(payload: T) -> Self {
+        Self(Rc::new(payload))
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    pub fn is<T: 'static>(&self) -> bool {
+        self.0.is::<T>()
+    }
+
+    fn type_id(&self) -> TypeId {
+        (*self.0).type_id()
+    }
+}
+
+impl fmt::Debug for DragPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DragPayload").finish_non_exhaustive()
+    }
+}
+
+/// Sent along with every drag event
+#[derive(Debug, Clone)]
+pub struct DragInfo {
+    pub payload: DragPayload,
+    /// Widget the drag started on, None for files dragged in from outside of the app
+    pub source: Option<UntypedHandle>,
+    /// Mouse position in the window (physical pixels)
+    pub position: (f32, f32),
+}
+
+/// Payload of files dragged onto the window from the OS, accept it with
+/// `ctx.accept_drops::<DroppedFiles>()`
+///
+/// Not every platform reports the mouse position while files are dragged over the window,
+/// the last known one is used to find the drop target then
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedFiles(pub Vec<PathBuf>);
+
+impl DroppedFiles {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.0
+    }
+
+    /// The files that look like images by their extension, ready for `load_texture`
+    pub fn images(&self) -> impl Iterator<Item = &PathBuf> {
+        self.0.iter().filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+        })
+    }
+}
+
+/// File drag from the OS, as reported by winit. Every file gets its own event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileDrop {
+    Hovered(PathBuf),
+    Dropped(PathBuf),
+    /// The files were dragged back out of the window or the drag was cancelled
+    Cancelled,
+}
+
+/// What follows the mouse around while dragging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DragPreview {
+    /// The dragged widget is drawn a second time at the mouse
+    #[default]
+    Widget,
+    None,
+}
+
+pub(crate) struct ActiveDrag {
+    /// None for files from the OS
+    pub(crate) source: Option<UntypedHandle>,
+    pub(crate) payload: DragPayload,
+    pub(crate) preview: DragPreview,
+    /// Where the widget was grabbed relative to its origin
+    pub(crate) grab_offset: (f32, f32),
+    /// Drop target under the mouse
+    pub(crate) hovered: Option<UntypedHandle>,
+}
+
+/// Drag and drop state of a view
+#[derive(Default)]
+pub(crate) struct DragManager {
+    /// Drag source the mouse went down on, the drag starts once it moved far enough
+    pub(crate) pressed: Option<UntypedHandle>,
+    pub(crate) active: Option<ActiveDrag>,
+    /// Payload types every drop target accepts, empty if it takes anything
+    pub(crate) targets: AHashMap<UntypedHandle, Vec<TypeId>>,
+    /// Cancelling is left to the view so the events go out in the usual order
+    pub(crate) cancel_requested: bool,
+    /// The OS dropped the files being dragged, there's no mouse button to watch for those
+    pub(crate) files_dropped: bool,
+}
+
+impl DragManager {
+    pub(crate) fn accept<T: 'static>(&mut self, handle: UntypedHandle) {
+        let types = self.targets.entry(handle).or_default();
+        if !types.contains(&TypeId::of::<T>()) {
+            types.push(TypeId::of::<T>());
+        }
+    }
+
+    pub(crate) fn accept_any(&mut self, handle: UntypedHandle) {
+        self.targets.insert(handle, Vec::new());
+    }
+
+    /// Forgets everything about a removed widget, a drag it started is cancelled silently
+    pub(crate) fn remove(&mut self, handle: UntypedHandle) {
+        self.targets.remove(&handle);
+
+        if self.pressed == Some(handle) {
+            self.pressed = None;
+        }
+
+        if let Some(drag) = &mut self.active {
+            if drag.source == Some(handle) {
+                self.active = None;
+            } else if drag.hovered == Some(handle) {
+                drag.hovered = None;
+            }
+        }
+    }
+
+    /// The drop target under `position` which takes `payload`, nested and
+    /// frontmost targets win over the ones behind them
+    pub(crate) fn target_at(
+        &self,
+        position: (f32, f32),
+        payload: &DragPayload,
+        source: Option<UntypedHandle>,
+        layout_of: impl Fn(UntypedHandle) -> Option<Layout>,
+    ) -> Option<UntypedHandle> {
+        let targets = self.targets
+            .iter()
+            .filter(|(handle, types)| Some(**handle) != source && (types.is_empty() || types.contains(&payload.type_id())))
+            .map(|(handle, _)| *handle);
+
+        widget_at(targets, position, layout_of)
+    }
+
+    /// Turns file drags from the OS into drags without a source widget
+    pub(crate) fn receive_files(&mut self, events: Vec<FileDrop>) {
+        let mut hovered = Vec::new();
+        let mut dropped = Vec::new();
+
+        for event in events {
+            match event {
+                FileDrop::Hovered(path) => hovered.push(path),
+                FileDrop::Dropped(path) => dropped.push(path),
+                FileDrop::Cancelled => {
+                    hovered.clear();
+                    if self.is_external() {
+                        self.cancel_requested = true;
+                    }
+                }
+            }
+        }
+
+        // Files can be dropped without ever hovering first, the drag then starts and ends in the same frame
+        for (files, was_dropped) in [(hovered, false), (dropped, true)] {
+            if files.is_empty() { continue }
+
+            let payload = DragPayload::new(DroppedFiles(files));
+
+            match &mut self.active {
+                Some(drag) if drag.source.is_none() => drag.payload = payload,
+                // Can't be dragging in the app while the mouse is busy dragging files around
+                Some(_) => continue,
+                None => {
+                    self.cancel_requested = false;
+                    self.active = Some(ActiveDrag {
+                        source: None,
+                        payload,
+                        preview: DragPreview::None,
+                        grab_offset: (0.0, 0.0),
+                        hovered: None,
+                    });
+                }
+            }
+
+            self.files_dropped |= was_dropped;
+        }
+    }
+
+    fn is_external(&self) -> bool {
+        self.active.as_ref().is_some_and(|drag| drag.source.is_none())
+    }
+}
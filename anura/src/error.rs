@@ -1,12 +1,84 @@
+use std::{error::Error, io};
+
+use crate::handle::UntypedHandle;
+
+pub type BoxedError = Box<dyn Error + Send + Sync>;
+
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum AnuraError<T> {
-    FatalError { msg: T },
+    /// A file on disk or an asset which was supposed to be loaded (font, texture) doesnt exist
+    AssetNotFound { asset: String, source: Option<io::Error> },
+    /// The asset exists but its contents are not something we can use
+    DecodeFailure { asset: String, source: Option<BoxedError> },
+    /// The handle doesnt point at a widget (anymore)
+    InvalidHandle { handle: UntypedHandle },
+    /// The renderer lost its surface, usually fixed by resizing
+    SurfaceLost { msg: T },
+    /// A shader didn't compile or a pipeline using it couldn't be created
+    ShaderCompile { msg: T },
+    /// A value passed to anura can't be used, e.g. a NaN font size
+    InvalidArgument { msg: String },
+    /// A glyph bitmap is bigger than a whole atlas page, the glyph is left out of any text
+    GlyphTooLarge { glyph: u16, size: (u32, u32), page_size: u32 },
+    /// The system clipboard couldn't be opened or written to
+    Clipboard(BoxedError),
+    /// Errors coming from user code
+    User(BoxedError),
+}
+
+impl<T> AnuraError<T> {
+    pub fn user(err: impl Into<BoxedError>) -> Self {
+        AnuraError::User(err.into())
+    }
 }
 
-impl<T> std::fmt::Display for AnuraError<T> {
+impl<T: std::fmt::Display> std::fmt::Display for AnuraError<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Anura Error").finish()
+        match self {
+            AnuraError::AssetNotFound { asset, .. } => write!(f, "asset not found: {asset}"),
+            AnuraError::DecodeFailure { asset, .. } => write!(f, "failed to decode asset: {asset}"),
+            AnuraError::InvalidHandle { handle } => write!(f, "invalid widget handle (index {})", handle.index),
+            AnuraError::SurfaceLost { msg } => write!(f, "renderer surface lost: {msg}"),
+            AnuraError::ShaderCompile { msg } => write!(f, "shader compilation failed: {msg}"),
+            AnuraError::InvalidArgument { msg } => write!(f, "invalid argument: {msg}"),
+            AnuraError::GlyphTooLarge { glyph, size, page_size } => {
+                write!(f, "glyph {glyph} ({}x{}) doesn't fit on a {page_size}x{page_size} atlas page", size.0, size.1)
+            }
+            AnuraError::Clipboard(err) => write!(f, "clipboard error: {err}"),
+            AnuraError::User(err) => write!(f, "{err}"),
+        }
     }
 }
 
-impl<T: std::fmt::Display + std::fmt::Debug> std::error::Error for AnuraError<T> {}
+impl<T: std::fmt::Display + std::fmt::Debug> Error for AnuraError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AnuraError::AssetNotFound { source, .. } => source.as_ref().map(|err| err as _),
+            AnuraError::DecodeFailure { source, .. } => source.as_ref().map(|err| err.as_ref() as _),
+            AnuraError::Clipboard(err) | AnuraError::User(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Where an error reported to `App::handle_error` came from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorOrigin {
+    App,
+    /// A view, identified by its id in the view stack
+    View(usize),
+    Widget { view: usize, handle: UntypedHandle },
+    Render,
+}
+
+/// What to do after an error has been handled
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ErrorAction {
+    #[default]
+    Ignore,
+    /// Stops sending events to the widget which caused the error,
+    /// ignored if the error didnt come from a widget
+    DisableWidget,
+    Exit,
+}
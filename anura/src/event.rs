@@ -1,7 +1,7 @@
 // I think these should be non exhaustive since they will be matched
 // "client" side and handling them is optional
 
-use crate::{widget::Layout};
+use crate::{widget::Layout, animation::AnimationId, task::{TaskId, TaskOutput}, drag::DragInfo, gesture::Gesture};
 
 
 // I think this distinction between view, app and widget events is pointless
@@ -15,16 +15,65 @@ pub enum AppEvent {
     Init,
     Update,
     Exit,
+    /// The os suspended the app (mostly relevant on mobile)
+    Suspended,
+    Resumed,
+    Focused(bool),
+    Minimized,
+    /// Window is no longer minimized
+    Restored,
+    /// A shortcut bound in `ActionScope::App` was pressed
+    Action(String),
+    /// Every frame of the recording passed to `AnuraApp::replay` has been played
+    ReplayFinished,
 }
 
 #[non_exhaustive]
 pub enum ViewEvent {
     Init,
     Update,
+    /// A task spawned by the view has finished
+    TaskFinished { id: TaskId, output: TaskOutput },
+    /// A shortcut bound in `ActionScope::View` was pressed or a widget sent one
+    /// with `AnuraContext::send_action`, e.g. a clicked `Button`
+    Action(String),
 }
 
 #[non_exhaustive]
 pub enum WidgetEvent {
     Init,
     Update,
+    /// One of the animations targeting this widget has finished
+    AnimationFinished(AnimationId),
+    /// A task spawned by this widget has finished
+    TaskFinished { id: TaskId, output: TaskOutput },
+    /// Text being composed with an input method (e.g. for chinese or japanese), only sent
+    /// to the focused widget. An empty text means the composition was cancelled or committed
+    ImePreedit {
+        text: String,
+        /// Byte range of the ime's cursor inside `text`, None if it should be hidden
+        cursor: Option<(usize, usize)>,
+    },
+    /// Finished text from an input method, only sent to the focused widget
+    ImeCommit(String),
+    /// A shortcut bound in `ActionScope::Focused` was pressed, only sent to the focused widget
+    Action(String),
+    /// A drag this drop target accepts moved onto it
+    DragEnter(DragInfo),
+    /// Sent every frame a drag is over this drop target, after `DragEnter`
+    DragOver(DragInfo),
+    /// The drag moved off this drop target or was cancelled
+    DragLeave(DragInfo),
+    /// The drag was let go over this drop target
+    Drop(DragInfo),
+    /// A drag started by this widget is over, `dropped` is false if nobody took it
+    DragEnd { dropped: bool },
+    /// A touch gesture which started on this widget, see `AnuraContext::accept_gestures`
+    Gesture(Gesture),
+}
+
+/// User event of the winit event loop, used to wake it up from other threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnuraUserEvent {
+    TaskFinished,
 }
\ No newline at end of file
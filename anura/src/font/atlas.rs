@@ -0,0 +1,247 @@
+use ahash::AHashSet;
+
+use crate::graphics::AnuraRenderer;
+
+/// Space left around every glyph so neighbours dont bleed in when sampling
+const PADDING: u32 = 1;
+
+/// A row of glyphs inside of a page, glyphs are placed left to right
+#[derive(Debug, Clone)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+pub struct AtlasPage<R: AnuraRenderer> {
+    /// Only created once the page gets uploaded for the first time
+    pub texture: Option<R::TextureHandle>,
+    /// RGBA, empty while the page is unused
+    data: Vec<u8>,
+    shelves: Vec<Shelf>,
+    /// Where the next shelf would start
+    shelf_end: u32,
+    dirty: bool,
+    pub last_used: u64,
+}
+
+impl<R: AnuraRenderer> AtlasPage<R> {
+    fn new() -> Self {
+        Self {
+            texture: None,
+            data: Vec::new(),
+            shelves: Vec::new(),
+            shelf_end: 0,
+            dirty: false,
+            last_used: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shelves.is_empty()
+    }
+
+    /// Finds a spot for a `w * h` rect (padding included) on this page
+    fn allocate(&mut self, w: u32, h: u32, page_size: u32) -> Option<(u32, u32)> {
+        if w > page_size || h > page_size { return None }
+
+        // Tightest shelf which still has room
+        let best = self.shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= h && shelf.x + w <= page_size)
+            .min_by_key(|shelf| shelf.height);
+
+        let fits_new_shelf = self.shelf_end + h <= page_size;
+
+        match best {
+            // Dont waste a tall shelf on a tiny glyph if a fitting shelf can still be opened
+            Some(shelf) if !(fits_new_shelf && shelf.height > h + h / 2) => {
+                let pos = (shelf.x, shelf.y);
+                shelf.x += w;
+                Some(pos)
+            }
+            _ if fits_new_shelf => {
+                self.shelves.push(Shelf { y: self.shelf_end, height: h, x: w });
+                self.shelf_end += h;
+                Some((0, self.shelf_end - h))
+            }
+            _ => None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.data = Vec::new();
+        self.shelves.clear();
+        self.shelf_end = 0;
+        self.dirty = false;
+    }
+}
+
+/// Where a bitmap ended up inside of the atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRegion {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Texture atlas which is filled up as glyphs are needed, shelf packed across
+/// as many equally sized pages as it takes
+pub struct GlyphAtlas<R: AnuraRenderer> {
+    pub page_size: u32,
+    pub pages: Vec<AtlasPage<R>>,
+    /// Bumped every frame, used to find pages which werent used for a while
+    pub frame: u64,
+    /// Pages marked as used since the last `take_touched`
+    touched: AHashSet<usize>,
+}
+
+impl<R: AnuraRenderer> GlyphAtlas<R> {
+    pub fn new(page_size: u32) -> Self {
+        Self {
+            page_size,
+            pages: Vec::new(),
+            frame: 0,
+            touched: AHashSet::new(),
+        }
+    }
+
+    /// Copies an RGBA bitmap into the atlas, opening a new page if none has room left
+    ///
+    /// Returns `None` if the bitmap is bigger than a whole page
+    pub fn insert(&mut self, width: u32, height: u32, rgba: &[u8]) -> Option<AtlasRegion> {
+        let (w, h) = (width + PADDING * 2, height + PADDING * 2);
+        if w > self.page_size || h > self.page_size { return None }
+
+        let page_size = self.page_size;
+
+        let found = self.pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(index, page)| page.allocate(w, h, page_size).map(|pos| (index, pos)));
+
+        let (page, (x, y)) = match found {
+            Some(found) => found,
+            None => {
+                self.pages.push(AtlasPage::new());
+                let index = self.pages.len() - 1;
+                let pos = self.pages[index].allocate(w, h, page_size)?;
+                (index, pos)
+            }
+        };
+
+        let region = AtlasRegion {
+            page,
+            x: x + PADDING,
+            y: y + PADDING,
+            width,
+            height,
+        };
+
+        self.write(region, rgba);
+        Some(region)
+    }
+
+    fn write(&mut self, region: AtlasRegion, rgba: &[u8]) {
+        let page_size = self.page_size as usize;
+        let page = &mut self.pages[region.page];
+
+        // Pages only take up memory once something is on them
+        if page.data.is_empty() {
+            page.data = vec![0; page_size * page_size * 4];
+        }
+
+        let row_len = region.width as usize * 4;
+        for row in 0..region.height as usize {
+            let start = ((region.y as usize + row) * page_size + region.x as usize) * 4;
+            page.data[start..start + row_len].copy_from_slice(&rgba[row * row_len..(row + 1) * row_len]);
+        }
+
+        page.dirty = true;
+    }
+
+    /// Empties every page, their textures are kept around and reused
+    pub fn clear(&mut self) {
+        for page in &mut self.pages {
+            page.clear();
+        }
+    }
+
+    pub fn mark_used(&mut self, page: usize) {
+        self.pages[page].last_used = self.frame;
+        self.touched.insert(page);
+    }
+
+    /// Pages marked as used since the last call, used to find out which pages
+    /// the render commands of a widget point at
+    pub(crate) fn take_touched(&mut self) -> AHashSet<usize> {
+        std::mem::take(&mut self.touched)
+    }
+
+    /// Normalized uv rect (x, y, w, h) of a region
+    pub fn uv(&self, region: &AtlasRegion) -> (f32, f32, f32, f32) {
+        let size = self.page_size as f32;
+        (
+            region.x as f32 / size,
+            region.y as f32 / size,
+            region.width as f32 / size,
+            region.height as f32 / size,
+        )
+    }
+
+    /// Sends every page which changed since the last upload to the renderer
+    pub fn upload(&mut self, renderer: &mut R) {
+        let page_size = self.page_size;
+
+        for page in self.pages.iter_mut().filter(|page| page.dirty) {
+            match &page.texture {
+                Some(texture) => renderer.update_texture(texture.clone(), &page.data),
+                None => page.texture = Some(renderer.new_texture(&page.data, (page_size, page_size), None)),
+            }
+            page.dirty = false;
+        }
+    }
+
+    /// Frees pages which werent used during the last `max_age` frames, as long as
+    /// the atlas has more than `max_pages` pages with something on them.
+    /// Pages in `referenced` are still drawn by cached render commands and are never freed
+    ///
+    /// Returns the indices of the freed pages
+    pub fn evict(&mut self, renderer: &mut R, max_pages: usize, max_age: u64, referenced: &AHashSet<usize>) -> Vec<usize> {
+        let mut in_use = self.pages.iter().filter(|page| !page.is_empty()).count();
+        if in_use <= max_pages { return Vec::new() }
+
+        let mut stale = self.pages
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !referenced.contains(index))
+            .filter(|(_, page)| !page.is_empty() && self.frame.saturating_sub(page.last_used) > max_age)
+            .map(|(index, page)| (page.last_used, index))
+            .collect::<Vec<_>>();
+
+        // Oldest first
+        stale.sort();
+
+        let mut evicted = Vec::new();
+        for (_, index) in stale {
+            if in_use <= max_pages { break }
+
+            let page = &mut self.pages[index];
+            page.clear();
+            if let Some(texture) = page.texture.take() {
+                renderer.remove_texture(texture);
+            }
+
+            evicted.push(index);
+            in_use -= 1;
+        }
+
+        evicted
+    }
+
+    pub fn page_texture(&self, page: usize) -> Option<&R::TextureHandle> {
+        self.pages.get(page).and_then(|page| page.texture.as_ref())
+    }
+}
@@ -0,0 +1,695 @@
+use std::ops::Range;
+
+use unicode_linebreak::linebreaks;
+
+use crate::graphics::AnuraRenderer;
+
+use super::{FontAtlas, ShapedGlyph};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces of every wrapped line so it fills the whole width,
+    /// last lines of paragraphs are left aligned
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextWrap {
+    /// Breaks lines at unicode line break opportunities (in between words)
+    #[default]
+    Word,
+    /// Breaks lines in between any two graphemes
+    Grapheme,
+    /// Only explicit newlines start a new line
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextOverflow {
+    /// Lines which dont fit are not shown
+    #[default]
+    Clip,
+    /// Same as clip but the last visible line ends with an ellipsis if anything got cut off
+    Ellipsis,
+    /// Text is moved up by `TextLayoutOptions::scroll_offset`, lines out of bounds are not shown
+    Scroll,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextLayoutOptions {
+    pub max_width: Option<f32>,
+    pub max_height: Option<f32>,
+    /// Distance between baselines, font size + 5px if not set
+    pub line_height: Option<f32>,
+    pub align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    pub wrap: TextWrap,
+    pub overflow: TextOverflow,
+    /// Only used with `TextOverflow::Scroll`
+    pub scroll_offset: f32,
+}
+
+impl TextLayoutOptions {
+    pub fn bounded(bounds: Option<(f32, f32)>) -> Self {
+        Self {
+            max_width: bounds.map(|b| b.0),
+            max_height: bounds.map(|b| b.1),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    /// Face of the font the glyph comes from, see `ShapedGlyph::face`
+    pub face: usize,
+    pub glyph_id: u16,
+    /// Byte index into the laid out text
+    pub cluster: usize,
+    /// Position on the baseline relative to the top left of the layout
+    pub x: f32,
+    pub y: f32,
+    pub advance: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutLine {
+    /// Range into `TextLayout::glyphs`
+    pub glyphs: Range<usize>,
+    /// Byte range of the text on this line, without the newline
+    pub text: Range<usize>,
+    pub baseline: f32,
+    /// Where the line starts after alignment
+    pub x: f32,
+    /// Width without trailing whitespace
+    pub width: f32,
+    /// False if the line got clipped away by the overflow mode
+    pub visible: bool,
+}
+
+/// Text broken up into lines and aligned, ready to be turned into a mesh
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<LayoutLine>,
+    pub line_height: f32,
+    /// Size the text was shaped at, can differ from the atlas size for sdf fonts
+    pub font_size: f32,
+    /// Size of the whole text, including the lines which are not visible
+    pub size: (f32, f32),
+    /// True if some of the text is not visible
+    pub truncated: bool,
+}
+
+/// Size of a piece of text, see `FontManager::measure`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TextMetrics {
+    pub size: (f32, f32),
+    pub line_count: usize,
+    /// Byte index of where every line but the first one starts, both at newlines and wraps
+    pub line_breaks: Vec<usize>,
+}
+
+impl From<&TextLayout> for TextMetrics {
+    fn from(layout: &TextLayout) -> Self {
+        Self {
+            size: layout.size,
+            line_count: layout.lines.len(),
+            line_breaks: layout.lines.iter().skip(1).map(|line| line.text.start).collect(),
+        }
+    }
+}
+
+/// Line after breaking but before alignment
+struct RawLine {
+    glyphs: Vec<(ShapedGlyph, bool)>,
+    text: Range<usize>,
+    last_in_paragraph: bool,
+}
+
+impl RawLine {
+    fn width(&self) -> f32 {
+        line_width(&self.glyphs)
+    }
+}
+
+impl TextLayout {
+    pub fn new<R: AnuraRenderer>(font: &FontAtlas<R>, text: &str, options: &TextLayoutOptions) -> Self {
+        Self::with_size(font, font.size, text, options)
+    }
+
+    /// Lays the text out at a size other than the one of the atlas, only makes sense for sdf fonts
+    pub fn with_size<R: AnuraRenderer>(font: &FontAtlas<R>, font_size: f32, text: &str, options: &TextLayoutOptions) -> Self {
+        let line_height = options.line_height.unwrap_or(font_size + 5.0);
+
+        let mut raw_lines = break_lines(font, font_size, text, options);
+
+        let content_height = raw_lines.len() as f32 * line_height;
+
+        //Vertical alignment only makes sense if theres space left
+        let mut offset_y = match (options.max_height, options.vertical_align) {
+            (Some(max_height), VerticalAlign::Center) => ((max_height - content_height) / 2.0).max(0.0),
+            (Some(max_height), VerticalAlign::Bottom) => (max_height - content_height).max(0.0),
+            _ => 0.0,
+        };
+
+        if options.overflow == TextOverflow::Scroll {
+            offset_y -= options.scroll_offset;
+        }
+
+        let baselines = (0..raw_lines.len())
+            .map(|i| offset_y + i as f32 * line_height + font_size)
+            .collect::<Vec<_>>();
+
+        let visible = baselines
+            .iter()
+            .map(|baseline| match options.max_height {
+                Some(max_height) => baseline - font_size >= -0.01 && *baseline <= max_height + 0.01,
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        let mut truncated = visible.iter().any(|v| !v);
+
+        // Cut off lines which are too long when not wrapping
+        if let (TextWrap::None, Some(max_width)) = (options.wrap, options.max_width) {
+            for line in &mut raw_lines {
+                if line.width() <= max_width { continue }
+
+                truncated = true;
+                match options.overflow {
+                    TextOverflow::Ellipsis => append_ellipsis(font, font_size, line, max_width),
+                    _ => {
+                        let mut x = 0.0;
+                        line.glyphs.retain(|(glyph, _)| {
+                            x += glyph.x_advance;
+                            x <= max_width
+                        });
+                    }
+                }
+            }
+        }
+
+        if options.overflow == TextOverflow::Ellipsis && truncated {
+            let last_visible = visible.iter().rposition(|v| *v);
+            let cut_below = last_visible.is_some_and(|index| index + 1 < visible.len());
+
+            if let (Some(index), true) = (last_visible, cut_below) {
+                let max_width = options.max_width.unwrap_or(f32::INFINITY);
+                append_ellipsis(font, font_size, &mut raw_lines[index], max_width);
+            }
+        }
+
+        let layout_width = options.max_width.unwrap_or_else(|| {
+            raw_lines.iter().map(|line| line.width()).fold(0.0, f32::max)
+        });
+
+        let mut layout = TextLayout {
+            glyphs: Vec::new(),
+            lines: Vec::new(),
+            line_height,
+            font_size,
+            size: (0.0, content_height),
+            truncated,
+        };
+
+        for (index, line) in raw_lines.into_iter().enumerate() {
+            let wrapped = options.max_width.is_some() && options.wrap != TextWrap::None;
+            let aligned = align_line(&line.glyphs, layout_width, options.align, wrapped && !line.last_in_paragraph);
+
+            let baseline = baselines[index];
+            let first_glyph = layout.glyphs.len();
+            let mut x = aligned.x;
+
+            for ((glyph, _), advance) in line.glyphs.iter().zip(aligned.advances) {
+                layout.glyphs.push(PositionedGlyph {
+                    face: glyph.face,
+                    glyph_id: glyph.glyph_id,
+                    cluster: glyph.cluster,
+                    x: x + glyph.x_offset,
+                    y: baseline - glyph.y_offset,
+                    advance,
+                });
+
+                x += advance;
+            }
+
+            let width = aligned.width;
+            layout.size.0 = layout.size.0.max(width);
+
+            layout.lines.push(LayoutLine {
+                glyphs: first_glyph..layout.glyphs.len(),
+                text: line.text,
+                baseline,
+                x: aligned.x,
+                width,
+                visible: visible[index],
+            });
+        }
+
+        layout
+    }
+
+    pub fn visible_glyphs(&self) -> impl Iterator<Item = &PositionedGlyph> + '_ {
+        self.lines
+            .iter()
+            .filter(|line| line.visible)
+            .flat_map(|line| &self.glyphs[line.glyphs.clone()])
+    }
+
+    /// Top of a line, the line box goes from there down `line_height`
+    pub fn line_top(&self, line: usize) -> f32 {
+        self.lines[line].baseline - self.font_size
+    }
+
+    /// Line closest to a y position relative to the top of the layout
+    pub fn line_at(&self, y: f32) -> Option<usize> {
+        if self.lines.is_empty() { return None }
+
+        let index = self.lines
+            .iter()
+            .rposition(|line| line.baseline - self.font_size <= y)
+            .unwrap_or(0);
+
+        Some(index)
+    }
+
+    /// A wrapped line ends where the next one starts, that index belongs to the next line
+    fn is_wrapped(&self, line: usize) -> bool {
+        self.lines
+            .get(line + 1)
+            .is_some_and(|next| next.text.start == self.lines[line].text.end)
+    }
+
+    /// Places where a cursor can go on a line as (byte index, x), in visual order
+    fn caret_stops(&self, line: usize) -> Vec<(usize, f32)> {
+        let layout_line = &self.lines[line];
+        let glyphs = &self.glyphs[layout_line.glyphs.clone()];
+
+        // Marks and ligatures share their cluster with the glyph before them
+        let mut stops = Vec::with_capacity(glyphs.len() + 1);
+        for glyph in glyphs {
+            if stops.iter().all(|(cluster, _)| *cluster != glyph.cluster) {
+                stops.push((glyph.cluster, glyph.x));
+            }
+        }
+
+        if !self.is_wrapped(line) {
+            let end_x = glyphs.last().map_or(layout_line.x, |glyph| glyph.x + glyph.advance);
+            stops.push((layout_line.text.end, end_x));
+        }
+
+        stops
+    }
+
+    /// Byte index of the cursor position closest to a point relative to the top left of the layout,
+    /// this is what clicking into text should select
+    ///
+    /// Ligatures are treated as a single character
+    pub fn position_to_index(&self, position: (f32, f32)) -> usize {
+        let line = match self.line_at(position.1) {
+            Some(line) => line,
+            None => return 0,
+        };
+
+        self.caret_stops(line)
+            .into_iter()
+            .min_by(|a, b| (a.1 - position.0).abs().total_cmp(&(b.1 - position.0).abs()))
+            .map_or(self.lines[line].text.start, |(index, _)| index)
+    }
+
+    /// Where a cursor in front of the byte index goes, relative to the top left of the layout
+    ///
+    /// The y position is the top of the line, see `line_top`
+    pub fn index_to_position(&self, index: usize) -> (f32, f32) {
+        if self.lines.is_empty() {
+            return (0.0, 0.0);
+        }
+
+        let line = self.lines
+            .iter()
+            .enumerate()
+            .position(|(i, line)| index < line.text.end || (index == line.text.end && !self.is_wrapped(i)))
+            .unwrap_or(self.lines.len() - 1);
+
+        // Indices inside of a cluster snap to its start
+        let x = self.caret_stops(line)
+            .into_iter()
+            .filter(|(cluster, _)| *cluster <= index)
+            .max_by_key(|(cluster, _)| *cluster)
+            .map_or(self.lines[line].x, |(_, x)| x);
+
+        (x, self.line_top(line))
+    }
+}
+
+/// Shapes every paragraph and breaks it up into lines according to the wrap mode
+fn break_lines<R: AnuraRenderer>(font: &FontAtlas<R>, font_size: f32, text: &str, options: &TextLayoutOptions) -> Vec<RawLine> {
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+
+    for paragraph in text.split('\n') {
+        let content = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+
+        let shaped = font.shape_at(content, font_size)
+            .into_iter()
+            .map(|mut glyph| {
+                let whitespace = is_whitespace_at(content, glyph.cluster);
+
+                glyph.cluster += paragraph_start;
+                (glyph, whitespace)
+            })
+            .collect::<Vec<_>>();
+
+        let break_opportunities = match options.wrap {
+            TextWrap::Word => linebreaks(content)
+                .map(|(index, _)| index + paragraph_start)
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+
+        let starts = wrap_paragraph(&shaped, &break_opportunities, options.wrap, options.max_width);
+
+        for (i, line_start) in starts.iter().enumerate() {
+            let line_end = starts.get(i + 1).copied().unwrap_or(shaped.len());
+            let last_in_paragraph = line_end == shaped.len();
+
+            let text_start = shaped.get(*line_start).map_or(paragraph_start, |(g, _)| g.cluster);
+            let text_end = match last_in_paragraph {
+                true => paragraph_start + content.len(),
+                false => shaped[line_end].0.cluster,
+            };
+
+            lines.push(RawLine {
+                glyphs: shaped[*line_start..line_end].to_vec(),
+                text: text_start..text_end,
+                last_in_paragraph,
+            });
+        }
+
+        paragraph_start += paragraph.len() + 1;
+    }
+
+    lines
+}
+
+/// Breaks a shaped paragraph up according to the wrap mode, returns the index of
+/// the first glyph of every line. Glyphs come with a flag telling if they are whitespace
+pub(crate) fn wrap_paragraph(
+    shaped: &[(ShapedGlyph, bool)],
+    break_opportunities: &[usize],
+    wrap: TextWrap,
+    max_width: Option<f32>,
+) -> Vec<usize> {
+    let wrap_width = match wrap {
+        TextWrap::None => None,
+        _ => max_width,
+    };
+
+    let mut starts = vec![0];
+    let mut line_start = 0;
+    let mut last_break = None;
+    let mut x = 0.0;
+
+    for i in 0..shaped.len() {
+        let (glyph, whitespace) = shaped[i];
+
+        let can_break = i > line_start && glyph.grapheme_start && match wrap {
+            TextWrap::Word => break_opportunities.binary_search(&glyph.cluster).is_ok(),
+            TextWrap::Grapheme => true,
+            TextWrap::None => false,
+        };
+
+        if can_break {
+            last_break = Some(i);
+        }
+
+        if let Some(max_width) = wrap_width {
+            // Whitespace is allowed to hang past the edge
+            if x + glyph.x_advance > max_width && i > line_start && !whitespace {
+                //Fall back to breaking in between graphemes if a single word doesnt fit
+                let break_at = last_break
+                    .filter(|b| *b > line_start)
+                    .or_else(|| (line_start + 1..=i).rev().find(|j| shaped[*j].0.grapheme_start))
+                    .unwrap_or(i);
+
+                starts.push(break_at);
+
+                line_start = break_at;
+                last_break = None;
+                x = shaped[break_at..i].iter().map(|(g, _)| g.x_advance).sum();
+            }
+        }
+
+        x += glyph.x_advance;
+    }
+
+    starts
+}
+
+/// True if the glyph starting at `cluster` is whitespace, see `wrap_paragraph`
+pub(crate) fn is_whitespace_at(text: &str, cluster: usize) -> bool {
+    text[cluster..].chars().next().is_some_and(char::is_whitespace)
+}
+
+/// Width of a line without its trailing whitespace
+pub(crate) fn line_width(glyphs: &[(ShapedGlyph, bool)]) -> f32 {
+    let trailing = glyphs.iter().rev().take_while(|(_, whitespace)| *whitespace).count();
+    glyphs[..glyphs.len() - trailing]
+        .iter()
+        .map(|(glyph, _)| glyph.x_advance)
+        .sum()
+}
+
+/// Horizontal placement of a line, see `align_line`
+pub(crate) struct AlignedLine {
+    /// Where the line starts
+    pub(crate) x: f32,
+    /// Width without trailing whitespace, the full available width if justified
+    pub(crate) width: f32,
+    /// Advance of every glyph, spaces inside the line are stretched when justifying
+    pub(crate) advances: Vec<f32>,
+}
+
+/// Aligns a line within `available_width`, `justify` is false for lines which
+/// shouldn't be stretched like the last one of a paragraph
+pub(crate) fn align_line(
+    glyphs: &[(ShapedGlyph, bool)],
+    available_width: f32,
+    align: HorizontalAlign,
+    justify: bool,
+) -> AlignedLine {
+    let trailing = glyphs.iter().rev().take_while(|(_, whitespace)| *whitespace).count();
+    let inner = &glyphs[..glyphs.len() - trailing];
+
+    let width = line_width(glyphs);
+    let extra = (available_width - width).max(0.0);
+    let inner_spaces = inner.iter().filter(|(_, whitespace)| *whitespace).count();
+
+    let (x, space_stretch) = match align {
+        HorizontalAlign::Left => (0.0, 0.0),
+        HorizontalAlign::Center => (extra / 2.0, 0.0),
+        HorizontalAlign::Right => (extra, 0.0),
+        HorizontalAlign::Justify if justify && inner_spaces > 0 => (0.0, extra / inner_spaces as f32),
+        HorizontalAlign::Justify => (0.0, 0.0),
+    };
+
+    let advances = glyphs
+        .iter()
+        .enumerate()
+        .map(|(i, (glyph, whitespace))| match *whitespace && i < inner.len() {
+            true => glyph.x_advance + space_stretch,
+            false => glyph.x_advance,
+        })
+        .collect();
+
+    AlignedLine {
+        x,
+        width: if space_stretch > 0.0 { available_width } else { width },
+        advances,
+    }
+}
+
+/// Removes glyphs from the end of the line until an ellipsis fits and adds it
+fn append_ellipsis<R: AnuraRenderer>(font: &FontAtlas<R>, font_size: f32, line: &mut RawLine, max_width: f32) {
+    let mut ellipsis = font.shape_at("\u{2026}", font_size);
+    // Not every font has the ellipsis character
+    if ellipsis.iter().any(|glyph| glyph.glyph_id == 0) {
+        ellipsis = font.shape_at("...", font_size);
+    }
+
+    let ellipsis_width = ellipsis.iter().map(|glyph| glyph.x_advance).sum::<f32>();
+
+    while !line.glyphs.is_empty() && line.width() + ellipsis_width > max_width {
+        line.glyphs.pop();
+    }
+    // Dont leave the ellipsis floating after a space
+    while line.glyphs.last().is_some_and(|(_, whitespace)| *whitespace) {
+        line.glyphs.pop();
+    }
+
+    let cluster = line.glyphs.last().map_or(line.text.start, |(glyph, _)| glyph.cluster);
+
+    line.glyphs.extend(ellipsis.into_iter().map(|mut glyph| {
+        glyph.cluster = cluster;
+        (glyph, false)
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::shape_line;
+
+    static FIRA_SANS: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Regular.ttf");
+
+    fn glyph(cluster: usize, x: f32, baseline: f32) -> PositionedGlyph {
+        PositionedGlyph { face: 0, glyph_id: 1, cluster, x, y: baseline, advance: 10.0 }
+    }
+
+    fn line(glyphs: Range<usize>, text: Range<usize>, baseline: f32, width: f32) -> LayoutLine {
+        LayoutLine { glyphs, text, baseline, x: 0.0, width, visible: true }
+    }
+
+    /// "ab cd" wrapped after the space, every glyph 10px wide
+    fn wrapped() -> TextLayout {
+        let glyphs = vec![
+            glyph(0, 0.0, 10.0),
+            glyph(1, 10.0, 10.0),
+            glyph(2, 20.0, 10.0),
+            glyph(3, 0.0, 25.0),
+            glyph(4, 10.0, 25.0),
+        ];
+
+        TextLayout {
+            glyphs,
+            lines: vec![line(0..3, 0..3, 10.0, 20.0), line(3..5, 3..5, 25.0, 20.0)],
+            line_height: 15.0,
+            font_size: 10.0,
+            size: (20.0, 30.0),
+            truncated: false,
+        }
+    }
+
+    /// "fix" with "fi" drawn as a single 20px ligature
+    fn ligature() -> TextLayout {
+        let glyphs = vec![
+            PositionedGlyph { advance: 20.0, ..glyph(0, 0.0, 10.0) },
+            glyph(2, 20.0, 10.0),
+        ];
+
+        TextLayout {
+            glyphs,
+            lines: vec![line(0..2, 0..3, 10.0, 30.0)],
+            line_height: 15.0,
+            font_size: 10.0,
+            size: (30.0, 15.0),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn wrap_point_belongs_to_the_next_line() {
+        let layout = wrapped();
+
+        assert_eq!(layout.index_to_position(2), (20.0, 0.0));
+        assert_eq!(layout.index_to_position(3), (0.0, 15.0));
+        assert_eq!(layout.index_to_position(5), (20.0, 15.0));
+    }
+
+    #[test]
+    fn clicks_past_a_wrapped_line_stay_on_it() {
+        let layout = wrapped();
+
+        // The end of the first line is the start of the second, so the last stop is before the space
+        assert_eq!(layout.position_to_index((100.0, 5.0)), 2);
+        assert_eq!(layout.position_to_index((100.0, 20.0)), 5);
+        assert_eq!(layout.position_to_index((-5.0, 20.0)), 3);
+    }
+
+    #[test]
+    fn ligatures_are_a_single_caret_stop() {
+        let layout = ligature();
+
+        assert_eq!(layout.position_to_index((9.0, 5.0)), 0);
+        assert_eq!(layout.position_to_index((11.0, 5.0)), 2);
+        assert_eq!(layout.index_to_position(1), (0.0, 0.0));
+        assert_eq!(layout.index_to_position(2), (20.0, 0.0));
+        assert_eq!(layout.index_to_position(3), (30.0, 0.0));
+    }
+
+    #[test]
+    fn shaping_merges_ligatures_into_one_cluster() {
+        let glyphs = shape_line(FIRA_SANS, 0, 18.0, "fix");
+
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(glyphs.iter().map(|glyph| glyph.cluster).collect::<Vec<_>>(), [0, 2]);
+        assert!(glyphs.iter().all(|glyph| glyph.grapheme_start));
+    }
+
+    fn shaped(text: &str) -> Vec<(ShapedGlyph, bool)> {
+        text.char_indices()
+            .map(|(cluster, ch)| {
+                let glyph = ShapedGlyph {
+                    face: 0,
+                    glyph_id: 1,
+                    cluster,
+                    grapheme_start: true,
+                    x_advance: 10.0,
+                    y_advance: 0.0,
+                    x_offset: 0.0,
+                    y_offset: 0.0,
+                };
+                (glyph, ch.is_whitespace())
+            })
+            .collect()
+    }
+
+    fn word_breaks(text: &str) -> Vec<usize> {
+        linebreaks(text).map(|(index, _)| index).collect()
+    }
+
+    #[test]
+    fn words_wrap_at_break_opportunities() {
+        let text = "ab cd ef";
+        let starts = wrap_paragraph(&shaped(text), &word_breaks(text), TextWrap::Word, Some(55.0));
+
+        assert_eq!(starts, [0, 6]);
+    }
+
+    #[test]
+    fn trailing_whitespace_hangs_past_the_edge() {
+        let text = "ab cd";
+        let starts = wrap_paragraph(&shaped(text), &word_breaks(text), TextWrap::Word, Some(20.0));
+
+        assert_eq!(starts, [0, 3]);
+    }
+
+    #[test]
+    fn long_words_break_between_graphemes() {
+        let text = "abcdef";
+        let starts = wrap_paragraph(&shaped(text), &word_breaks(text), TextWrap::Word, Some(25.0));
+
+        assert_eq!(starts, [0, 2, 4]);
+    }
+
+    #[test]
+    fn unwrapped_text_stays_on_one_line() {
+        let text = "ab cd ef";
+        let starts = wrap_paragraph(&shaped(text), &word_breaks(text), TextWrap::None, Some(25.0));
+
+        assert_eq!(starts, [0]);
+    }
+}
@@ -0,0 +1,688 @@
+use std::{path::{Path, PathBuf}, sync::OnceLock};
+
+use ahash::{AHashMap, AHashSet};
+use fontdue::Metrics;
+use ordered_float::NotNan;
+
+use crate::{graphics::AnuraRenderer, error::AnuraError};
+
+mod shaping;
+pub use shaping::*;
+
+mod layout;
+pub use layout::*;
+
+mod system;
+pub use system::*;
+
+mod atlas;
+pub use atlas::*;
+
+mod sdf;
+pub use sdf::*;
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Font {
+    #[default]
+    Default,
+    /// The default font at another size than the one it was loaded at
+    DefaultSized(f32),
+    System(SystemFont, f32),
+    Path(PathBuf, f32)
+}
+
+impl Font {
+    /// Same font at a different size
+    pub fn with_size(&self, size: f32) -> Font {
+        match self {
+            Font::Default | Font::DefaultSized(_) => Font::DefaultSized(size),
+            Font::System(font, _) => Font::System(font.clone(), size),
+            Font::Path(path, _) => Font::Path(path.clone(), size),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct GlyphData {
+    pub uv: (f32, f32, f32, f32),
+    pub metrics: Metrics,
+    /// Atlas page the glyph is on, see `FontAtlas::page_texture`
+    pub page: usize,
+}
+
+impl GlyphData {
+    /// Whitespace and the like dont have a bitmap
+    pub fn is_empty(&self) -> bool {
+        self.metrics.width == 0 || self.metrics.height == 0
+    }
+}
+
+/// How glyph edges are smoothed when rasterizing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Antialiasing {
+    /// Hard edges, mostly useful for pixel fonts
+    None,
+    #[default]
+    Grayscale,
+}
+
+impl Antialiasing {
+    /// Rasterizes a glyph into RGBA with the coverage in every channel
+    fn rasterize(self, font: &fontdue::Font, glyph_id: u16, size: f32) -> (Metrics, Vec<u8>) {
+        let (metrics, coverage) = font.rasterize_indexed(glyph_id, size);
+
+        let rgba = coverage
+            .iter()
+            .map(|&value| match self {
+                Antialiasing::None if value >= 128 => 255,
+                Antialiasing::None => 0,
+                Antialiasing::Grayscale => value,
+            })
+            .flat_map(|value| [value; 4])
+            .collect();
+
+        (metrics, rgba)
+    }
+}
+
+/// Atlases with more pages than this start evicting the ones which werent used recently
+const MAX_PAGES: usize = 4;
+/// How many frames a page has to go unused before it can be evicted
+const EVICT_AFTER_FRAMES: u64 = 300;
+
+pub struct FontAtlas<R: AnuraRenderer> {
+    pub size: f32,
+    /// Every glyph rasterized so far, keyed by face and glyph id rather than char
+    /// since shaping can produce glyphs which dont map to any char (ligatures, alternates)
+    pub glyph_data: AHashMap<(usize, u16), GlyphData>,
+    pub atlas: GlyphAtlas<R>,
+    /// Glyphs are stored as signed distance fields which can be drawn at any size
+    pub sdf: bool,
+    antialiasing: Antialiasing,
+    /// Set when every glyph got thrown out, e.g. after changing the antialiasing
+    cleared: bool,
+    /// Bumped whenever glyphs get thrown out, meshes built with an older generation
+    /// might point at atlas space which holds other glyphs now
+    generation: u64,
+    /// Glyphs which couldn't be put into the atlas, see `FontManager::take_errors`
+    errors: Vec<AnuraError<R::ErrorMessage>>,
+    /// The font itself
+    face: FontFace,
+    /// The font itself followed by its fallbacks, only filled once the font is missing a glyph
+    faces: OnceLock<Vec<FontFace>>,
+    /// Where `faces` come from if they weren't passed in
+    fallbacks: Option<SystemFallbacks>,
+    /// One per face used so far, `None` for fallbacks which failed to load
+    rasterizers: Vec<Option<fontdue::Font>>,
+}
+
+impl<R: AnuraRenderer> FontAtlas<R> {
+    pub fn new(data: &[u8], size: f32) -> Result<Self, AnuraError<R::ErrorMessage>> {
+        Self::with_fallbacks(vec![FontFace::new(data.to_vec())], size)
+    }
+
+    /// Atlas of the first face, glyphs it doesnt have are taken from the other ones in order
+    ///
+    /// Nothing is rasterized up front, glyphs are added to the atlas the first time they are used
+    pub fn with_fallbacks(faces: Vec<FontFace>, size: f32) -> Result<Self, AnuraError<R::ErrorMessage>> {
+        let face = match faces.first() {
+            Some(face) => face.clone(),
+            None => return Err(AnuraError::DecodeFailure { asset: "font".to_owned(), source: None }),
+        };
+
+        Self::with_faces(face, OnceLock::from(faces), None, size)
+    }
+
+    /// Atlas whose fallbacks are looked up among the installed fonts the first time `face` is missing a glyph
+    pub(crate) fn with_system_fallbacks(
+        face: FontFace,
+        fallbacks: SystemFallbacks,
+        size: f32,
+    ) -> Result<Self, AnuraError<R::ErrorMessage>> {
+        Self::with_faces(face, OnceLock::new(), Some(fallbacks), size)
+    }
+
+    fn with_faces(
+        face: FontFace,
+        faces: OnceLock<Vec<FontFace>>,
+        fallbacks: Option<SystemFallbacks>,
+        size: f32,
+    ) -> Result<Self, AnuraError<R::ErrorMessage>> {
+        // Only the font itself has to work, broken fallbacks are skipped
+        let rasterizer = face.rasterizer(size).map_err(|err| AnuraError::DecodeFailure {
+            asset: "font".to_owned(),
+            source: Some(err.into()),
+        })?;
+
+        // Roomy enough for a decent amount of glyphs per page, even for big sizes
+        let page_size = ((size * 16.0) as u32).clamp(256, 2048).next_power_of_two();
+
+        Ok(Self {
+            size,
+            glyph_data: AHashMap::new(),
+            atlas: GlyphAtlas::new(page_size),
+            sdf: false,
+            antialiasing: Antialiasing::default(),
+            cleared: false,
+            generation: 0,
+            errors: Vec::new(),
+            face,
+            faces,
+            fallbacks,
+            rasterizers: vec![Some(rasterizer)],
+        })
+    }
+
+    /// Atlas storing glyphs as distance fields, draw them with `SDF_SHADER`
+    pub fn sdf(faces: Vec<FontFace>) -> Result<Self, AnuraError<R::ErrorMessage>> {
+        let mut atlas = Self::with_fallbacks(faces, SDF_SIZE)?;
+        atlas.sdf = true;
+        Ok(atlas)
+    }
+
+    /// Sdf atlas of the same font and fallbacks
+    fn sdf_of(&self) -> Result<Self, AnuraError<R::ErrorMessage>> {
+        let mut atlas = Self::with_faces(self.face.clone(), self.faces.clone(), self.fallbacks.clone(), SDF_SIZE)?;
+        atlas.sdf = true;
+        Ok(atlas)
+    }
+
+    pub fn antialiasing(&self) -> Antialiasing {
+        self.antialiasing
+    }
+
+    /// Glyphs rasterized so far are thrown out and rasterized again the next time they are used
+    ///
+    /// Sdf atlases always use their distance field so this does nothing for them
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        if self.antialiasing == antialiasing { return }
+        self.antialiasing = antialiasing;
+
+        if self.sdf || self.glyph_data.is_empty() { return }
+
+        self.glyph_data.clear();
+        self.atlas.clear();
+        self.cleared = true;
+        self.generation += 1;
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Shapes a single line of text with this font's size
+    pub fn shape(&self, text: &str) -> Vec<ShapedGlyph> {
+        self.shape_at(text, self.size)
+    }
+
+    pub fn shape_at(&self, text: &str, size: f32) -> Vec<ShapedGlyph> {
+        if let Some(faces) = self.faces.get() {
+            return shape_with_fallback(faces, size, text);
+        }
+
+        let glyphs = shape_with_fallback(std::slice::from_ref(&self.face), size, text);
+        if glyphs.iter().all(|glyph| glyph.glyph_id != 0) { return glyphs }
+
+        shape_with_fallback(self.faces(), size, text)
+    }
+
+    /// The font itself followed by its fallbacks, loading them if that didnt happen yet
+    fn faces(&self) -> &[FontFace] {
+        self.faces.get_or_init(|| {
+            let mut faces = vec![self.face.clone()];
+            faces.extend(self.fallbacks.iter().flat_map(|fallbacks| fallbacks.load()));
+            faces
+        })
+    }
+
+    /// Looks up a glyph, rasterizing it into the atlas the first time its needed
+    ///
+    /// New glyphs only show up on the gpu after `upload`
+    pub fn glyph(&mut self, face: usize, glyph_id: u16) -> Option<GlyphData> {
+        if let Some(glyph) = self.glyph_data.get(&(face, glyph_id)).copied() {
+            if !glyph.is_empty() {
+                self.atlas.mark_used(glyph.page);
+            }
+            return Some(glyph);
+        }
+
+        // Fallbacks get their rasterizers once a glyph of theirs is used
+        while self.rasterizers.len() <= face {
+            let fallback = self.faces.get().and_then(|faces| faces.get(self.rasterizers.len()))?;
+            self.rasterizers.push(fallback.rasterizer(self.size).ok());
+        }
+
+        let font = self.rasterizers[face].as_ref()?;
+
+        let (metrics, rgba_bitmap) = if self.sdf {
+            let (mut metrics, coverage) = font.rasterize_indexed(glyph_id, self.size);
+
+            if metrics.width > 0 && metrics.height > 0 {
+                let (field, width, height) = coverage_to_sdf(&coverage, metrics.width, metrics.height);
+
+                // The field reaches past the glyph so the quad has to as well
+                let spread = SDF_SPREAD as i32;
+                metrics.width = width;
+                metrics.height = height;
+                metrics.xmin -= spread;
+                metrics.ymin -= spread;
+
+                (metrics, field.iter().flat_map(|value| [*value; 4]).collect())
+            } else {
+                (metrics, Vec::new())
+            }
+        } else {
+            self.antialiasing.rasterize(font, glyph_id, self.size)
+        };
+
+        let mut glyph = GlyphData {
+            uv: (0.0, 0.0, 0.0, 0.0),
+            metrics,
+            page: 0,
+        };
+
+        if !glyph.is_empty() {
+            match self.atlas.insert(metrics.width as u32, metrics.height as u32, &rgba_bitmap) {
+                Some(region) => {
+                    glyph.uv = self.atlas.uv(&region);
+                    glyph.page = region.page;
+                    self.atlas.mark_used(region.page);
+                }
+                // Stored without a bitmap so it isnt rasterized and reported again every frame
+                None => {
+                    self.errors.push(AnuraError::GlyphTooLarge {
+                        glyph: glyph_id,
+                        size: (metrics.width as u32, metrics.height as u32),
+                        page_size: self.atlas.page_size,
+                    });
+                    glyph.metrics.width = 0;
+                    glyph.metrics.height = 0;
+                }
+            }
+        }
+
+        self.glyph_data.insert((face, glyph_id), glyph);
+        Some(glyph)
+    }
+
+    /// Sends newly rasterized glyphs to the renderer
+    pub fn upload(&mut self, renderer: &mut R) {
+        self.atlas.upload(renderer);
+    }
+
+    pub fn page_texture(&self, page: usize) -> Option<&R::TextureHandle> {
+        self.atlas.page_texture(page)
+    }
+
+    /// Evicts pages which werent used for a while and aren't in `referenced`
+    ///
+    /// Returns true if every glyph got thrown out, anything rendered before is invalid now
+    pub(crate) fn next_frame(&mut self, renderer: &mut R, referenced: &AHashSet<usize>) -> bool {
+        self.atlas.frame += 1;
+        self.atlas.take_touched();
+
+        let evicted = self.atlas.evict(renderer, MAX_PAGES, EVICT_AFTER_FRAMES, referenced);
+        if !evicted.is_empty() {
+            self.glyph_data.retain(|_, glyph| glyph.is_empty() || !evicted.contains(&glyph.page));
+            self.generation += 1;
+        }
+
+        std::mem::take(&mut self.cleared)
+    }
+}
+
+/// Size the bundled font is loaded at when nothing else was set as the default
+pub const DEFAULT_FONT_SIZE: f32 = 18.0;
+
+/// Fira Sans Regular, licensed under the SIL Open Font License (assets/fonts/FiraSans-LICENSE.txt)
+#[cfg(feature = "default-font")]
+pub static DEFAULT_FONT_DATA: &[u8] = include_bytes!("../../assets/fonts/FiraSans-Regular.ttf");
+
+pub struct FontManager<R: AnuraRenderer> {
+    pub fonts: Vec<FontAtlas<R>>,
+    pub path_map: AHashMap<(PathBuf, NotNan<f32>), usize>,
+    pub system_map: AHashMap<(SystemFont, NotNan<f32>), usize>,
+    pub default_font: Option<usize>,
+    /// Atlases of the default font at other sizes, built from the same face
+    pub default_sized: AHashMap<NotNan<f32>, usize>,
+    /// Index of a font loaded at `SDF_SIZE` to the index of its sdf atlas
+    pub sdf_map: AHashMap<usize, usize>,
+    pub system_fonts: SystemFontCache,
+    /// Installed families searched in order for glyphs a font doesnt have,
+    /// only applies to fonts loaded after changing it
+    pub fallback_families: Vec<String>,
+    /// Antialiasing of newly loaded fonts, see `set_antialiasing` for fonts which are already loaded
+    pub default_antialiasing: Antialiasing,
+}
+
+impl<R: AnuraRenderer> Default for FontManager<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: AnuraRenderer> FontManager<R> {
+    pub fn new() -> Self {
+        Self {
+            fonts: Vec::new(),
+            path_map: AHashMap::new(),
+            system_map: AHashMap::new(),
+            default_font: None,
+            default_sized: AHashMap::new(),
+            sdf_map: AHashMap::new(),
+            system_fonts: SystemFontCache::default(),
+            fallback_families: vec![
+                "DejaVu Sans".to_owned(),
+                "Noto Sans".to_owned(),
+                "Liberation Sans".to_owned(),
+            ],
+            default_antialiasing: Antialiasing::default(),
+        }
+    }
+
+    pub fn load_font(&mut self, font: &Font, default: bool) -> Result<(), AnuraError<R::ErrorMessage>> {
+        match font {
+            Font::Default => {
+                #[cfg(feature = "default-font")]
+                if self.default_font.is_none() {
+                    self.set_default_font_bytes(DEFAULT_FONT_DATA.to_vec(), DEFAULT_FONT_SIZE)?;
+                }
+
+                return Ok(());
+            },
+            Font::DefaultSized(size) => {
+                let key = size_key(*size, || "default font".to_owned())?;
+
+                if self.default_sized.contains_key(&key) { return Ok(()) }
+
+                self.load_font(&Font::Default, false)?;
+                let face = self.fonts[self.font_index(&Font::Default)?].face.clone();
+
+                let mut font_atlas = FontAtlas::with_system_fallbacks(face, self.fallbacks(None), *size)?;
+                font_atlas.set_antialiasing(self.default_antialiasing);
+                self.fonts.push(font_atlas);
+
+                self.default_sized.insert(key, self.fonts.len() - 1);
+            },
+            Font::Path(path, size) => {
+                let key = (path.to_path_buf(), size_key(*size, || path.display().to_string())?);
+
+                if self.path_map.contains_key(&key) { return Ok(()) }
+
+                let font_atlas = self.load_atlas(path, 0, *size)?;
+                self.fonts.push(font_atlas);
+
+                self.path_map.insert(key, self.fonts.len() - 1);
+            },
+            Font::System(system_font, size) => {
+                let key = (system_font.clone(), size_key(*size, || format!("system font \"{}\"", system_font.family))?);
+
+                if self.system_map.contains_key(&key) { return Ok(()) }
+
+                let (path, index) = self.system_fonts
+                    .find(system_font)
+                    .map(|face| (face.path.clone(), face.index))
+                    .ok_or_else(|| AnuraError::AssetNotFound {
+                        asset: format!("system font \"{}\"", system_font.family),
+                        source: None,
+                    })?;
+
+                let font_atlas = self.load_atlas(&path, index, *size)?;
+                self.fonts.push(font_atlas);
+
+                self.system_map.insert(key, self.fonts.len() - 1);
+            },
+        };
+        
+
+        if default {
+            self.default_font = Some(self.fonts.len() - 1);
+            self.default_sized.clear();
+        }
+
+        crate::debug!("Loaded a new font");
+
+        Ok(())
+    }
+
+    fn load_atlas(&mut self, path: &Path, index: u32, size: f32) -> Result<FontAtlas<R>, AnuraError<R::ErrorMessage>> {
+        let data = self.system_fonts.load(path).map_err(|err| AnuraError::AssetNotFound {
+            asset: path.display().to_string(),
+            source: Some(err),
+        })?;
+
+        let face = FontFace { data, index };
+        let fallbacks = self.fallbacks(Some(path));
+
+        let mut font_atlas = FontAtlas::with_system_fallbacks(face, fallbacks, size).map_err(|err| match err {
+            //Attach the path so its obvious which font is broken
+            AnuraError::DecodeFailure { source, .. } => AnuraError::DecodeFailure {
+                asset: path.display().to_string(),
+                source,
+            },
+            err => err,
+        })?;
+        font_atlas.set_antialiasing(self.default_antialiasing);
+
+        Ok(font_atlas)
+    }
+
+    /// Makes a font which isnt on disk (e.g. embedded with `include_bytes!`) the one used by `Font::Default`
+    pub fn set_default_font_bytes(&mut self, data: Vec<u8>, size: f32) -> Result<(), AnuraError<R::ErrorMessage>> {
+        let mut font_atlas = FontAtlas::with_system_fallbacks(FontFace::new(data), self.fallbacks(None), size).map_err(|err| match err {
+            AnuraError::DecodeFailure { source, .. } => AnuraError::DecodeFailure {
+                asset: "default font".to_owned(),
+                source,
+            },
+            err => err,
+        })?;
+        font_atlas.set_antialiasing(self.default_antialiasing);
+        self.fonts.push(font_atlas);
+
+        self.default_font = Some(self.fonts.len() - 1);
+        self.default_sized.clear();
+
+        Ok(())
+    }
+
+    /// The fallback families, they are only looked up once a font is missing a glyph
+    fn fallbacks(&self, font_path: Option<&Path>) -> SystemFallbacks {
+        SystemFallbacks {
+            system_fonts: self.system_fonts.clone(),
+            families: self.fallback_families.clone(),
+            skip: font_path.map(Path::to_path_buf),
+        }
+    }
+
+    /// Loads the sdf atlas of a font, one atlas is shared by all sizes of the same font
+    pub fn load_sdf_font(&mut self, font: &Font) -> Result<(), AnuraError<R::ErrorMessage>> {
+        //The sdf atlas is built from the same faces as the regular one
+        let base = font.with_size(SDF_SIZE);
+        self.load_font(&base, false)?;
+
+        let base_index = self.font_index(&base)?;
+        if self.sdf_map.contains_key(&base_index) { return Ok(()) }
+
+        let font_atlas = self.fonts[base_index].sdf_of()?;
+        self.fonts.push(font_atlas);
+
+        self.sdf_map.insert(base_index, self.fonts.len() - 1);
+
+        Ok(())
+    }
+
+    pub fn get_sdf_font_mut(&mut self, font: &Font) -> Result<&mut FontAtlas<R>, AnuraError<R::ErrorMessage>> {
+        let base_index = self.font_index(&font.with_size(SDF_SIZE))?;
+
+        let index = *self.sdf_map.get(&base_index).ok_or_else(|| AnuraError::AssetNotFound {
+            asset: "sdf atlas of font (not loaded)".to_owned(),
+            source: None,
+        })?;
+
+        Ok(&mut self.fonts[index])
+    }
+
+    /// Changes the antialiasing of an already loaded font
+    pub fn set_antialiasing(&mut self, font: &Font, antialiasing: Antialiasing) -> Result<(), AnuraError<R::ErrorMessage>> {
+        self.get_font_mut(font)?.set_antialiasing(antialiasing);
+        Ok(())
+    }
+
+    /// Lays out text with the same metrics widgets render it with, the font has to be loaded
+    pub fn layout(&self, font: &Font, text: &str, options: &TextLayoutOptions) -> Result<TextLayout, AnuraError<R::ErrorMessage>> {
+        let size = self.font_size(font)?;
+        Ok(TextLayout::with_size(self.get_font(font)?, size, text, options))
+    }
+
+    /// How much space text takes up, wrapped at `max_width` if its set
+    pub fn measure(&self, text: &str, font: &Font, max_width: Option<f32>) -> Result<TextMetrics, AnuraError<R::ErrorMessage>> {
+        let options = TextLayoutOptions {
+            max_width,
+            ..Default::default()
+        };
+
+        Ok(TextMetrics::from(&self.layout(font, text, &options)?))
+    }
+
+    /// Size text in this font is drawn at
+    pub fn font_size(&self, font: &Font) -> Result<f32, AnuraError<R::ErrorMessage>> {
+        match font {
+            Font::Default => Ok(self.get_font(font)?.size),
+            Font::DefaultSized(size) | Font::System(_, size) | Font::Path(_, size) => Ok(*size),
+        }
+    }
+
+    pub fn get_font(&self, font: &Font) -> Result<&FontAtlas<R>, AnuraError<R::ErrorMessage>> {
+        Ok(&self.fonts[self.font_index(font)?])
+    }
+
+    /// Mutable access is needed to look up glyphs since they get rasterized on demand
+    pub fn get_font_mut(&mut self, font: &Font) -> Result<&mut FontAtlas<R>, AnuraError<R::ErrorMessage>> {
+        let index = self.font_index(font)?;
+        Ok(&mut self.fonts[index])
+    }
+
+    /// Index of a loaded font in `fonts`
+    pub fn font_index(&self, font: &Font) -> Result<usize, AnuraError<R::ErrorMessage>> {
+        let idx = match font {
+            Font::Default => self.default_font.ok_or_else(|| AnuraError::AssetNotFound {
+                asset: "default font (none was set and the default-font feature is off)".to_owned(),
+                source: None,
+            })?,
+            Font::DefaultSized(size) => {
+                let key = size_key(*size, || "default font".to_owned())?;
+
+                *self.default_sized.get(&key).ok_or_else(|| AnuraError::AssetNotFound {
+                    asset: format!("default font at size {size} (font not loaded)"),
+                    source: None,
+                })?
+            },
+            Font::Path(path, size) => {
+                let key = (path.to_path_buf(), size_key(*size, || path.display().to_string())?);
+
+                *self.path_map.get(&key).ok_or_else(|| AnuraError::AssetNotFound {
+                    asset: format!("{} at size {size} (font not loaded)", path.display()),
+                    source: None,
+                })?
+            },
+            Font::System(system_font, size) => {
+                let key = (system_font.clone(), size_key(*size, || format!("system font \"{}\"", system_font.family))?);
+
+                *self.system_map.get(&key).ok_or_else(|| AnuraError::AssetNotFound {
+                    asset: format!("system font \"{}\" at size {size} (font not loaded)", system_font.family),
+                    source: None,
+                })?
+            },
+        };
+
+        Ok(idx)
+    }
+
+    /// Called once per frame with the (font, page) pairs cached render commands draw from,
+    /// returns true if an atlas got cleared which means every cached render command has to be thrown away
+    pub fn next_frame(&mut self, renderer: &mut R, referenced: &[(usize, usize)]) -> bool {
+        let mut cleared = false;
+        for (index, font) in self.fonts.iter_mut().enumerate() {
+            let pages = referenced
+                .iter()
+                .filter(|(font, _)| *font == index)
+                .map(|(_, page)| *page)
+                .collect();
+
+            cleared |= font.next_frame(renderer, &pages);
+        }
+        cleared
+    }
+
+    /// (font, page) pairs of every atlas page marked as used since the last call
+    pub(crate) fn take_touched(&mut self) -> Vec<(usize, usize)> {
+        self.fonts
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(index, font)| font.atlas.take_touched().into_iter().map(move |page| (index, page)))
+            .collect()
+    }
+
+    /// Marks pages as used again, e.g. when a widget's cached render commands get drawn
+    pub(crate) fn touch(&mut self, pages: &[(usize, usize)]) {
+        for (font, page) in pages {
+            if let Some(font) = self.fonts.get_mut(*font) {
+                font.atlas.mark_used(*page);
+            }
+        }
+    }
+
+    /// Glyphs which couldn't be put into an atlas since the last call, they are left out of any text
+    pub(crate) fn take_errors(&mut self) -> Vec<AnuraError<R::ErrorMessage>> {
+        self.fonts.iter_mut().flat_map(|font| std::mem::take(&mut font.errors)).collect()
+    }
+}
+
+/// Font sizes are map keys, a NaN size can't be one
+fn size_key<T>(size: f32, asset: impl FnOnce() -> String) -> Result<NotNan<f32>, AnuraError<T>> {
+    NotNan::new(size).map_err(|_| AnuraError::InvalidArgument {
+        msg: format!("{} requested at size NaN", asset()),
+    })
+}
+
+#[cfg(all(test, feature = "wgpu-renderer"))]
+mod tests {
+    use super::*;
+    use crate::graphics::WgpuRenderer;
+
+    fn fonts() -> FontManager<WgpuRenderer> {
+        let mut fonts = FontManager::new();
+        fonts.set_default_font_bytes(include_bytes!("../../assets/fonts/FiraSans-Regular.ttf").to_vec(), 18.0).unwrap();
+        fonts
+    }
+
+    #[test]
+    fn measure_breaks_at_newlines() {
+        let metrics = fonts().measure("one two\nthree", &Font::Default, None).unwrap();
+
+        assert_eq!(metrics.line_count, 2);
+        assert_eq!(metrics.line_breaks, [8]);
+    }
+
+    #[test]
+    fn measure_wraps_at_max_width() {
+        let fonts = fonts();
+        let one_line = fonts.measure("one two", &Font::Default, None).unwrap();
+        let first_word = fonts.measure("one ", &Font::Default, None).unwrap();
+
+        let metrics = fonts.measure("one two", &Font::Default, Some(first_word.size.0 + 1.0)).unwrap();
+
+        assert_eq!(metrics.line_breaks, [4]);
+        assert_eq!(metrics.size.1, one_line.size.1 * 2.0);
+        assert!(metrics.size.0 < one_line.size.0);
+    }
+
+    #[test]
+    fn nan_font_sizes_are_invalid_arguments() {
+        let font = Font::Path(PathBuf::from("missing.ttf"), f32::NAN);
+
+        assert!(matches!(fonts().load_font(&font, false), Err(AnuraError::InvalidArgument { .. })));
+    }
+}
@@ -0,0 +1,146 @@
+/// Size glyphs of sdf atlases are rasterized at, the same atlas is then used for every size
+pub const SDF_SIZE: f32 = 64.0;
+/// How far (in pixels at `SDF_SIZE`) the distance field reaches out from the glyph edges,
+/// this also limits how wide outlines and glows can get
+pub const SDF_SPREAD: u32 = 8;
+
+/// Extra effects for sdf text, sizes are in pixels at the size the text is drawn at
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SdfEffects {
+    pub outline_width: f32,
+    pub outline_color: (f32, f32, f32, f32),
+    pub glow_radius: f32,
+    pub glow_color: (f32, f32, f32, f32),
+}
+
+impl SdfEffects {
+    pub fn outline(mut self, width: f32, color: (f32, f32, f32, f32)) -> Self {
+        self.outline_width = width;
+        self.outline_color = color;
+        self
+    }
+
+    pub fn glow(mut self, radius: f32, color: (f32, f32, f32, f32)) -> Self {
+        self.glow_radius = radius;
+        self.glow_color = color;
+        self
+    }
+
+    /// Outline width and glow radius converted into distance field units for text drawn at `size`
+    pub(crate) fn shader_params(&self, size: f32) -> [f32; 2] {
+        let to_sdf = |px: f32| (px * SDF_SIZE / size / (2.0 * SDF_SPREAD as f32)).clamp(0.0, 0.49);
+
+        let outline = to_sdf(self.outline_width);
+        // The glow starts where the outline ends
+        let glow = to_sdf(self.glow_radius).min(0.49 - outline);
+
+        [outline, glow]
+    }
+}
+
+/// Turns a coverage bitmap into a signed distance field padded by `SDF_SPREAD` on every side
+///
+/// 0.5 is the glyph edge, values above it are inside. Returns the field and its size
+pub(crate) fn coverage_to_sdf(coverage: &[u8], width: usize, height: usize) -> (Vec<u8>, usize, usize) {
+    let spread = SDF_SPREAD as usize;
+    let (w, h) = (width + spread * 2, height + spread * 2);
+
+    let inside = |x: usize, y: usize| {
+        x >= spread && y >= spread && x - spread < width && y - spread < height
+            && coverage[(y - spread) * width + (x - spread)] >= 128
+    };
+
+    let mut to_inside = DistanceGrid::new(w, h, inside);
+    let mut to_outside = DistanceGrid::new(w, h, |x, y| !inside(x, y));
+    to_inside.propagate();
+    to_outside.propagate();
+
+    let field = (0..w * h)
+        .map(|i| {
+            let distance = to_outside.distance(i) - to_inside.distance(i);
+            let value = 0.5 + distance / (2.0 * SDF_SPREAD as f32);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect();
+
+    (field, w, h)
+}
+
+/// 8SSEDT, every cell ends up with the offset to the closest seed cell
+struct DistanceGrid {
+    width: usize,
+    height: usize,
+    offsets: Vec<(i32, i32)>,
+}
+
+impl DistanceGrid {
+    const FAR: (i32, i32) = (9999, 9999);
+
+    fn new(width: usize, height: usize, seed: impl Fn(usize, usize) -> bool) -> Self {
+        let mut offsets = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                offsets.push(if seed(x, y) { (0, 0) } else { Self::FAR });
+            }
+        }
+
+        Self { width, height, offsets }
+    }
+
+    fn distance(&self, index: usize) -> f32 {
+        let (dx, dy) = self.offsets[index];
+        ((dx * dx + dy * dy) as f32).sqrt()
+    }
+
+    fn compare(&mut self, x: usize, y: usize, ox: i32, oy: i32) {
+        let (nx, ny) = (x as i32 + ox, y as i32 + oy);
+        if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 { return }
+
+        let (dx, dy) = self.offsets[ny as usize * self.width + nx as usize];
+        let candidate = (dx + ox, dy + oy);
+
+        let current = &mut self.offsets[y * self.width + x];
+        let length = |(dx, dy): (i32, i32)| dx * dx + dy * dy;
+
+        if length(candidate) < length(*current) {
+            *current = candidate;
+        }
+    }
+
+    fn propagate(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.compare(x, y, -1, 0);
+                self.compare(x, y, 0, -1);
+                self.compare(x, y, -1, -1);
+                self.compare(x, y, 1, -1);
+            }
+            for x in (0..self.width).rev() {
+                self.compare(x, y, 1, 0);
+            }
+        }
+
+        for y in (0..self.height).rev() {
+            for x in (0..self.width).rev() {
+                self.compare(x, y, 1, 0);
+                self.compare(x, y, 0, 1);
+                self.compare(x, y, -1, 1);
+                self.compare(x, y, 1, 1);
+            }
+            for x in 0..self.width {
+                self.compare(x, y, -1, 0);
+            }
+        }
+    }
+}
+
+/// How a text widget turns glyphs into pixels
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextRenderMode {
+    /// Glyphs rasterized at the exact font size, sharpest for static text
+    #[default]
+    Bitmap,
+    /// Glyphs drawn from a distance field, scales to any size without a new atlas
+    /// and supports outlines and glow
+    Sdf(SdfEffects),
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use rustybuzz::{Face, UnicodeBuffer};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A single positioned glyph coming out of the shaper, all values are in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    /// Which face of the font the glyph comes from, 0 is the font itself
+    /// and everything after that its fallbacks
+    pub face: usize,
+    pub glyph_id: u16,
+    /// Byte index into the shaped text of the cluster this glyph belongs to
+    pub cluster: usize,
+    /// Whether a line can be broken before this glyph without splitting a grapheme
+    pub grapheme_start: bool,
+    pub x_advance: f32,
+    pub y_advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+}
+
+/// Raw font file plus the index of the face inside of it (for font collections)
+///
+/// The bytes are shared, cloning a face doesnt copy the font file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFace {
+    pub data: Arc<[u8]>,
+    pub index: u32,
+}
+
+impl FontFace {
+    pub fn new(data: impl Into<Arc<[u8]>>) -> Self {
+        Self { data: data.into(), index: 0 }
+    }
+
+    pub(crate) fn rasterizer(&self, size: f32) -> Result<fontdue::Font, &'static str> {
+        let settings = fontdue::FontSettings {
+            scale: size,
+            collection_index: self.index,
+        };
+
+        fontdue::Font::from_bytes(&*self.data, settings)
+    }
+}
+
+/// Shapes a line with the first face, runs of glyphs it doesnt have are
+/// shaped again with the next face and so on
+pub fn shape_with_fallback(faces: &[FontFace], size: f32, text: &str) -> Vec<ShapedGlyph> {
+    let primary = match faces.first() {
+        Some(face) => face,
+        None => return Vec::new(),
+    };
+
+    let glyphs = shape_line(&primary.data, primary.index, size, text);
+    if faces.len() < 2 { return glyphs }
+
+    let mut result = Vec::with_capacity(glyphs.len());
+    let mut i = 0;
+
+    while i < glyphs.len() {
+        // Glyph 0 is .notdef, the font doesnt have that character
+        if glyphs[i].glyph_id != 0 {
+            result.push(glyphs[i]);
+            i += 1;
+            continue;
+        }
+
+        let run_start = i;
+        while i < glyphs.len() && glyphs[i].glyph_id == 0 {
+            i += 1;
+        }
+
+        let start = glyphs[run_start].cluster;
+        let end = glyphs.get(i).map_or(text.len(), |glyph| glyph.cluster);
+
+        // Clusters go backwards in right to left text, keep the missing glyphs there
+        if end <= start {
+            result.extend_from_slice(&glyphs[run_start..i]);
+            continue;
+        }
+
+        let mut fallback = shape_with_fallback(&faces[1..], size, &text[start..end]);
+
+        if fallback.iter().all(|glyph| glyph.glyph_id == 0) {
+            result.extend_from_slice(&glyphs[run_start..i]);
+            continue;
+        }
+
+        for glyph in &mut fallback {
+            glyph.cluster += start;
+            glyph.face += 1;
+        }
+        fallback[0].grapheme_start = glyphs[run_start].grapheme_start;
+
+        result.extend(fallback);
+    }
+
+    result
+}
+
+/// Shapes a single line of text, kerning, ligatures and mark positioning
+/// are all handled by rustybuzz
+pub fn shape_line(font_data: &[u8], face_index: u32, size: f32, text: &str) -> Vec<ShapedGlyph> {
+    let face = match Face::from_slice(font_data, face_index) {
+        Some(face) => face,
+        None => return Vec::new(),
+    };
+
+    let scale = size / face.units_per_em() as f32;
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.guess_segment_properties();
+
+    let glyph_buffer = rustybuzz::shape(&face, &[], buffer);
+
+    let grapheme_starts = text
+        .grapheme_indices(true)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    glyph_buffer.glyph_infos()
+        .iter()
+        .zip(glyph_buffer.glyph_positions())
+        .enumerate()
+        .map(|(i, (info, pos))| {
+            let cluster = info.cluster as usize;
+
+            // Glyphs after the first one of a cluster (e.g. decomposed marks) cant be broken before
+            let first_in_cluster = i == 0 || glyph_buffer.glyph_infos()[i - 1].cluster != info.cluster;
+
+            ShapedGlyph {
+                face: 0,
+                glyph_id: info.glyph_id as u16,
+                cluster,
+                grapheme_start: first_in_cluster && grapheme_starts.binary_search(&cluster).is_ok(),
+                x_advance: pos.x_advance as f32 * scale,
+                y_advance: pos.y_advance as f32 * scale,
+                x_offset: pos.x_offset as f32 * scale,
+                y_offset: pos.y_offset as f32 * scale,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,410 @@
+use std::{
+    env, fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use ahash::{AHashMap, AHashSet};
+use rustybuzz::ttf_parser::{name, name_id, os2, Language, Style};
+
+use super::FontFace;
+
+/// A font installed on the system, looked up by family name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SystemFont {
+    pub family: String,
+    /// 100 (thin) to 900 (black), 400 is regular and 700 bold
+    pub weight: u16,
+    pub italic: bool,
+}
+
+impl SystemFont {
+    pub fn new(family: impl Into<String>) -> Self {
+        Self {
+            family: family.into(),
+            weight: 400,
+            italic: false,
+        }
+    }
+
+    pub fn weight(mut self, weight: u16) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.weight(700)
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+}
+
+impl From<&str> for SystemFont {
+    fn from(family: &str) -> Self {
+        Self::new(family)
+    }
+}
+
+impl From<String> for SystemFont {
+    fn from(family: String) -> Self {
+        Self::new(family)
+    }
+}
+
+/// A single face found while scanning the font directories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemFace {
+    pub path: PathBuf,
+    /// Index of the face inside of a font collection (.ttc), 0 otherwise
+    pub index: u32,
+    pub family: String,
+    pub weight: u16,
+    pub italic: bool,
+    /// 1 (ultra condensed) to 9 (ultra expanded), 5 is normal
+    pub width: u16,
+}
+
+/// Finds fonts by scanning the usual font directories, the same ones fontconfig looks at
+///
+/// The directories are only scanned once, the first time a font is looked up.
+/// Clones share the scan as well as the font files which are loaded
+#[derive(Debug, Clone, Default)]
+pub struct SystemFontCache {
+    faces: Arc<OnceLock<Vec<SystemFace>>>,
+    /// Font files in use, fonts loaded from the same file share its bytes
+    files: Arc<Mutex<AHashMap<PathBuf, Weak<[u8]>>>>,
+}
+
+impl SystemFontCache {
+    pub fn faces(&self) -> &[SystemFace] {
+        self.faces.get_or_init(|| {
+            let mut faces = Vec::new();
+            let mut visited = AHashSet::new();
+            for dir in font_dirs() {
+                scan_dir(&dir, &mut visited, &mut faces);
+            }
+
+            crate::debug!("Found {} system font faces", faces.len());
+            faces
+        })
+    }
+
+    /// Rescans the font directories on the next lookup, e.g. after installing a font
+    pub fn invalidate(&mut self) {
+        self.faces = Arc::default();
+    }
+
+    /// Sorted names of every installed font family
+    pub fn families(&self) -> Vec<String> {
+        let mut families = self.faces()
+            .iter()
+            .map(|face| face.family.clone())
+            .collect::<Vec<_>>();
+
+        families.sort();
+        families.dedup();
+        families
+    }
+
+    /// The face of the family closest to the requested weight and style
+    pub fn find(&self, font: &SystemFont) -> Option<&SystemFace> {
+        self.faces()
+            .iter()
+            .filter(|face| face.family.eq_ignore_ascii_case(font.family.trim()))
+            .min_by_key(|face| (
+                face.italic != font.italic,
+                weight_distance(font.weight, face.weight),
+                // Condensed and expanded faces often share the family name
+                face.width.abs_diff(5),
+            ))
+    }
+
+    /// Reads a font file, or hands out the bytes of it if another font uses the file already
+    pub fn load(&self, path: &Path) -> io::Result<Arc<[u8]>> {
+        let mut files = self.files.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some(data) = files.get(path).and_then(Weak::upgrade) {
+            return Ok(data);
+        }
+
+        let data: Arc<[u8]> = fs::read(path)?.into();
+
+        files.retain(|_, file| file.strong_count() > 0);
+        files.insert(path.to_path_buf(), Arc::downgrade(&data));
+
+        Ok(data)
+    }
+}
+
+/// Installed families searched for glyphs a font doesnt have
+///
+/// Nothing is scanned or read until the font is actually missing a glyph
+#[derive(Debug, Clone)]
+pub(crate) struct SystemFallbacks {
+    pub(crate) system_fonts: SystemFontCache,
+    pub(crate) families: Vec<String>,
+    /// File of the font itself, which is no use as its own fallback
+    pub(crate) skip: Option<PathBuf>,
+}
+
+impl SystemFallbacks {
+    /// Faces of the families which are installed
+    pub(crate) fn load(&self) -> Vec<FontFace> {
+        let mut faces = Vec::new();
+
+        for family in &self.families {
+            let face = match self.system_fonts.find(&SystemFont::new(family.as_str())) {
+                Some(face) if Some(&face.path) != self.skip.as_ref() => face,
+                _ => continue,
+            };
+
+            // Missing fallbacks are not worth an error
+            if let Ok(data) = self.system_fonts.load(&face.path) {
+                faces.push(FontFace { data, index: face.index });
+            }
+        }
+
+        crate::debug!("Loaded {} fallback faces", faces.len());
+        faces
+    }
+}
+
+/// How far a face's weight is from the requested one, lighter faces are
+/// preferred for regular text and heavier ones for bold text like css does
+fn weight_distance(wanted: u16, weight: u16) -> u32 {
+    let distance = (wanted as i32 - weight as i32).unsigned_abs() * 2;
+
+    let wrong_side = match wanted <= 500 {
+        true => weight > wanted,
+        false => weight < wanted,
+    };
+
+    distance + wrong_side as u32
+}
+
+fn font_dirs() -> Vec<PathBuf> {
+    #[allow(unused_mut)]
+    let mut dirs = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        let home = env::var_os("HOME").map(PathBuf::from);
+
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+
+        match env::var_os("XDG_DATA_HOME") {
+            Some(data_home) => dirs.push(PathBuf::from(data_home).join("fonts")),
+            None => dirs.extend(home.as_ref().map(|home| home.join(".local/share/fonts"))),
+        }
+        dirs.extend(home.map(|home| home.join(".fonts")));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+        dirs.push(PathBuf::from("/Library/Fonts"));
+        dirs.extend(env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Fonts")));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let windir = env::var_os("WINDIR").map_or(PathBuf::from("C:\\Windows"), PathBuf::from);
+        dirs.push(windir.join("Fonts"));
+    }
+
+    dirs
+}
+
+fn scan_dir(dir: &Path, visited: &mut AHashSet<PathBuf>, faces: &mut Vec<SystemFace>) {
+    // Symlinks can point back up the tree
+    let canonical = match dir.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(_) => return,
+    };
+    if !visited.insert(canonical) { return }
+
+    // Missing directories are completely normal
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            scan_dir(&path, visited, faces);
+            continue;
+        }
+
+        let is_font = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "ttf" | "otf" | "ttc" | "otc"));
+
+        if !is_font { continue }
+
+        // Unreadable files are skipped
+        if let Ok(file_faces) = read_faces(&path) {
+            faces.extend(file_faces);
+        }
+    }
+}
+
+/// Reads the faces of a font file without loading all of it,
+/// only the table directories and the name and OS/2 tables are read
+fn read_faces(path: &Path) -> io::Result<Vec<SystemFace>> {
+    let mut file = File::open(path)?;
+    let header = read_at(&mut file, 0, 12)?;
+
+    // Collections start with the offsets of the table directory of every face
+    let offsets = match &header[..4] {
+        b"ttcf" => {
+            let count = be_u32(&header[8..]);
+            read_at(&mut file, 12, count as u64 * 4)?
+                .chunks_exact(4)
+                .map(be_u32)
+                .collect()
+        }
+        _ => vec![0],
+    };
+
+    let mut faces = Vec::new();
+
+    for (index, offset) in offsets.into_iter().enumerate() {
+        let records = match table_records(&mut file, offset) {
+            Some(records) => records,
+            None => continue,
+        };
+
+        let name_data = match read_table(&mut file, &records, b"name") {
+            Some(data) => data,
+            None => continue,
+        };
+
+        let family = match name::Table::parse(&name_data).and_then(|table| family_name(table.names)) {
+            Some(family) => family,
+            None => continue,
+        };
+
+        let os2_data = read_table(&mut file, &records, b"OS/2");
+        let os2 = os2_data.as_deref().and_then(os2::Table::parse);
+
+        faces.push(SystemFace {
+            path: path.to_path_buf(),
+            index: index as u32,
+            family,
+            weight: os2.map_or(400, |os2| os2.weight().to_number()),
+            italic: os2.is_some_and(|os2| os2.style() != Style::Normal),
+            width: os2.map_or(5, |os2| os2.width().to_number()),
+        });
+    }
+
+    Ok(faces)
+}
+
+/// Table records of the face whose table directory starts at `offset`, 16 bytes each
+fn table_records(file: &mut File, offset: u32) -> Option<Vec<u8>> {
+    let directory = read_at(file, offset as u64, 12).ok()?;
+    let count = u16::from_be_bytes([directory[4], directory[5]]);
+
+    read_at(file, offset as u64 + 12, count as u64 * 16).ok()
+}
+
+fn read_table(file: &mut File, records: &[u8], tag: &[u8; 4]) -> Option<Vec<u8>> {
+    let record = records.chunks_exact(16).find(|record| &record[..4] == tag)?;
+    read_at(file, be_u32(&record[8..]) as u64, be_u32(&record[12..]) as u64).ok()
+}
+
+/// Exactly `len` bytes starting at `offset`, fails if the file is shorter
+fn read_at(file: &mut File, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+
+    // Lengths come from the file itself, so nothing is allocated up front
+    let mut data = Vec::new();
+    file.by_ref().take(len).read_to_end(&mut data)?;
+
+    match data.len() as u64 == len {
+        true => Ok(data),
+        false => Err(io::ErrorKind::UnexpectedEof.into()),
+    }
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Typographic family if the font has one (groups all weights under one name), regular family otherwise
+fn family_name(names: name::Names) -> Option<String> {
+    for id in [name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY] {
+        let names = names
+            .into_iter()
+            .filter(|name| name.name_id == id && name.is_unicode())
+            .collect::<Vec<_>>();
+
+        let english = names.iter().find(|name| name.language() == Language::English_UnitedStates);
+
+        // Unicode names are stored as utf-16 big endian
+        if let Some(name) = english.or(names.first()) {
+            let units = name.name
+                .chunks_exact(2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+
+            if let Ok(family) = char::decode_utf16(units).collect::<Result<String, _>>() {
+                return Some(family);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts")
+    }
+
+    #[test]
+    fn faces_are_read_from_the_headers() {
+        let faces = read_faces(&assets().join("FiraSans-Regular.ttf")).unwrap();
+
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0].family, "Fira Sans");
+        assert_eq!(faces[0].weight, 400);
+        assert!(!faces[0].italic);
+        assert_eq!(faces[0].index, 0);
+    }
+
+    #[test]
+    fn scan_skips_non_fonts() {
+        let mut faces = Vec::new();
+        scan_dir(&assets(), &mut AHashSet::new(), &mut faces);
+
+        // The license next to it isnt a font
+        assert_eq!(faces.iter().map(|face| face.family.as_str()).collect::<Vec<_>>(), ["Fira Sans"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_survives_symlink_loops() {
+        let dir = env::temp_dir().join(format!("anura-font-scan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(assets().join("FiraSans-Regular.ttf"), dir.join("FiraSans-Regular.ttf")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+
+        let mut faces = Vec::new();
+        scan_dir(&dir, &mut AHashSet::new(), &mut faces);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(faces.len(), 1);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{collections::HashMap, time::Duration};
+
+use ahash::AHashSet;
+
+use crate::{handle::UntypedHandle, input::TouchPoint, widget::{widget_at, Layout}};
+
+/// How far (in pixels) a finger can move and still count as a tap or long press
+const TAP_SLOP: f32 = 10.0;
+/// Touches held longer than this aren't taps anymore
+const TAP_TIME: Duration = Duration::from_millis(300);
+const LONG_PRESS_TIME: Duration = Duration::from_millis(500);
+/// Pixels per second a finger has to be moving at when it's lifted for a swipe
+const SWIPE_MIN_SPEED: f32 = 800.0;
+const SWIPE_MIN_DISTANCE: f32 = 50.0;
+/// A finger resting longer than this before being lifted doesn't swipe
+const SWIPE_MAX_PAUSE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePhase {
+    Started,
+    Moved,
+    Ended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Touch gestures, positions are in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// A short touch which didn't move
+    Tap { position: (f32, f32) },
+    /// A finger held in place, sent once while it's still down
+    LongPress { position: (f32, f32) },
+    /// One finger moved across the screen
+    Pan {
+        phase: GesturePhase,
+        start: (f32, f32),
+        position: (f32, f32),
+        /// Movement since the last pan event
+        delta: (f32, f32),
+    },
+    /// Two fingers moved apart or together
+    Pinch {
+        phase: GesturePhase,
+        /// Halfway between the fingers
+        center: (f32, f32),
+        /// Zoom since the last pinch event, above 1 when the fingers move apart
+        scale: f32,
+    },
+    /// A finger flicked off the screen, sent after the pan it was part of ended
+    Swipe {
+        start: (f32, f32),
+        direction: SwipeDirection,
+        /// Pixels per second when the finger was lifted
+        velocity: (f32, f32),
+    },
+}
+
+impl Gesture {
+    /// None for gestures which happen all at once
+    pub fn phase(&self) -> Option<GesturePhase> {
+        match self {
+            Gesture::Pan { phase, .. } | Gesture::Pinch { phase, .. } => Some(*phase),
+            _ => None,
+        }
+    }
+
+    /// Where the gesture happened, used to find the widget it goes to
+    pub fn origin(&self) -> (f32, f32) {
+        match *self {
+            Gesture::Tap { position } | Gesture::LongPress { position } => position,
+            Gesture::Pan { start, .. } | Gesture::Swipe { start, .. } => start,
+            Gesture::Pinch { center, .. } => center,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+enum GestureState {
+    #[default]
+    Idle,
+    /// One finger down, could still become anything
+    Pressing { id: u64 },
+    LongPressed { id: u64 },
+    Panning { id: u64, last: (f32, f32) },
+    Pinching { ids: (u64, u64), last_distance: f32 },
+    /// A pinch ended with fingers still down, nothing starts until they're all lifted
+    Waiting,
+}
+
+/// Turns touch points into gestures, fed by `InputManager`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GestureRecognizer {
+    state: GestureState,
+}
+
+impl GestureRecognizer {
+    pub(crate) fn touch_started(&mut self, id: u64, touches: &HashMap<u64, TouchPoint>, gestures: &mut Vec<Gesture>) {
+        match self.state {
+            GestureState::Idle if touches.len() == 1 => self.state = GestureState::Pressing { id },
+            GestureState::Pressing { id: first } | GestureState::LongPressed { id: first } | GestureState::Panning { id: first, .. } => {
+                if let GestureState::Panning { last, .. } = self.state {
+                    if let Some(point) = touches.get(&first) {
+                        gestures.push(Gesture::Pan { phase: GesturePhase::Ended, start: point.start(), position: last, delta: (0.0, 0.0) });
+                    }
+                }
+
+                let (a, b) = match (touches.get(&first), touches.get(&id)) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return,
+                };
+
+                self.state = GestureState::Pinching { ids: (first, id), last_distance: distance(a.location(), b.location()) };
+                gestures.push(Gesture::Pinch { phase: GesturePhase::Started, center: center(a.location(), b.location()), scale: 1.0 });
+            }
+            _ => {}
+        }
+    }
+
+    /// `touches` are the ones still down, `cancelled` touches don't tap or swipe
+    pub(crate) fn touch_ended(&mut self, point: &TouchPoint, cancelled: bool, now: Duration, touches: &HashMap<u64, TouchPoint>, gestures: &mut Vec<Gesture>) {
+        let position = point.location();
+
+        match self.state {
+            GestureState::Pressing { id } if id == point.id => {
+                let moved = distance(point.start(), position);
+
+                if !cancelled {
+                    if moved <= TAP_SLOP && now.saturating_sub(point.started_at) <= TAP_TIME {
+                        gestures.push(Gesture::Tap { position });
+                    } else if let Some(swipe) = swipe(point, now) {
+                        gestures.push(swipe);
+                    }
+                }
+
+                self.state = GestureState::Idle;
+            }
+            GestureState::Panning { id, last } if id == point.id => {
+                let delta = (position.0 - last.0, position.1 - last.1);
+                gestures.push(Gesture::Pan { phase: GesturePhase::Ended, start: point.start(), position, delta });
+
+                if !cancelled {
+                    gestures.extend(swipe(point, now));
+                }
+
+                self.state = GestureState::Idle;
+            }
+            GestureState::LongPressed { id } if id == point.id => self.state = GestureState::Idle,
+            GestureState::Pinching { ids: (a, b), .. } if a == point.id || b == point.id => {
+                let other = if a == point.id { b } else { a };
+                let center = match touches.get(&other) {
+                    Some(other) => self::center(position, other.location()),
+                    None => position,
+                };
+
+                gestures.push(Gesture::Pinch { phase: GesturePhase::Ended, center, scale: 1.0 });
+                self.state = GestureState::Waiting;
+            }
+            _ => {}
+        }
+
+        if touches.is_empty() {
+            self.state = GestureState::Idle;
+        }
+    }
+
+    /// Called once a frame for everything which depends on how far fingers moved or how long they're down
+    pub(crate) fn update(&mut self, now: Duration, touches: &HashMap<u64, TouchPoint>, gestures: &mut Vec<Gesture>) {
+        match self.state {
+            GestureState::Pressing { id } => {
+                let point = match touches.get(&id) {
+                    Some(point) => point,
+                    None => return,
+                };
+                let (start, position) = (point.start(), point.location());
+
+                if distance(start, position) > TAP_SLOP {
+                    let delta = (position.0 - start.0, position.1 - start.1);
+                    gestures.push(Gesture::Pan { phase: GesturePhase::Started, start, position, delta });
+                    self.state = GestureState::Panning { id, last: position };
+                } else if now.saturating_sub(point.started_at) >= LONG_PRESS_TIME {
+                    gestures.push(Gesture::LongPress { position: start });
+                    self.state = GestureState::LongPressed { id };
+                }
+            }
+            GestureState::Panning { id, last } => {
+                let point = match touches.get(&id) {
+                    Some(point) => point,
+                    None => return,
+                };
+                let position = point.location();
+
+                if position != last {
+                    let delta = (position.0 - last.0, position.1 - last.1);
+                    gestures.push(Gesture::Pan { phase: GesturePhase::Moved, start: point.start(), position, delta });
+                    self.state = GestureState::Panning { id, last: position };
+                }
+            }
+            GestureState::Pinching { ids, last_distance } => {
+                let (a, b) = match (touches.get(&ids.0), touches.get(&ids.1)) {
+                    (Some(a), Some(b)) => (a.location(), b.location()),
+                    _ => return,
+                };
+                let current = distance(a, b);
+
+                if current != last_distance && last_distance > 0.0 {
+                    gestures.push(Gesture::Pinch { phase: GesturePhase::Moved, center: center(a, b), scale: current / last_distance });
+                    self.state = GestureState::Pinching { ids, last_distance: current };
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+fn center(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+}
+
+/// A swipe if the finger was lifted fast enough after moving far enough
+fn swipe(point: &TouchPoint, now: Duration) -> Option<Gesture> {
+    let (vx, vy) = point.velocity;
+
+    if vx.hypot(vy) < SWIPE_MIN_SPEED
+        || distance(point.start(), point.location()) < SWIPE_MIN_DISTANCE
+        || now.saturating_sub(point.updated_at) > SWIPE_MAX_PAUSE
+    {
+        return None;
+    }
+
+    let direction = match (vx.abs() > vy.abs(), vx > 0.0, vy > 0.0) {
+        (true, true, _) => SwipeDirection::Right,
+        (true, false, _) => SwipeDirection::Left,
+        (false, _, true) => SwipeDirection::Down,
+        (false, _, false) => SwipeDirection::Up,
+    };
+
+    Some(Gesture::Swipe { start: point.start(), direction, velocity: point.velocity })
+}
+
+/// Widgets taking gestures in a view
+#[derive(Default)]
+pub(crate) struct GestureTargets {
+    pub(crate) targets: AHashSet<UntypedHandle>,
+    /// Widget the pan or pinch in progress went to, the rest of it goes there too
+    pub(crate) captured: Option<UntypedHandle>,
+}
+
+impl GestureTargets {
+    pub(crate) fn remove(&mut self, handle: UntypedHandle) {
+        self.targets.remove(&handle);
+
+        if self.captured == Some(handle) {
+            self.captured = None;
+        }
+    }
+
+    /// Frontmost and innermost target under `position`
+    pub(crate) fn target_at(&self, position: (f32, f32), layout_of: impl Fn(UntypedHandle) -> Option<Layout>) -> Option<UntypedHandle> {
+        widget_at(self.targets.iter().copied(), position, layout_of)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::font::{FontAtlas, GlyphData};
+use crate::font::{FontAtlas, GlyphData, SdfEffects, TextLayout, TextLayoutOptions};
 
 use super::{
     AnuraMesh,
@@ -8,6 +8,10 @@ use super::{
 //Embed the default shaders :)
 pub static PLAIN_SHADER: &str = include_str!("../shaders/plain.wgsl");
 pub static IMAGE_SHADER: &str = include_str!("../shaders/image.wgsl");
+/// Tints the glyph atlas with the vertex colour
+pub static TEXT_SHADER: &str = include_str!("../shaders/text.wgsl");
+/// Vertex and fragment shader for `SdfVertex` meshes of sdf fonts
+pub static SDF_SHADER: &str = include_str!("../shaders/sdf.wgsl");
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable, PartialEq, Default)]
@@ -27,6 +31,33 @@ impl AnuraVertex for DefaultVertex {
     }
 }
 
+/// Vertex of sdf text, carries the effects so differently styled text can share an atlas
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable, PartialEq, Default)]
+pub struct SdfVertex {
+    pub pos: [f32; 3],
+    pub color: [f32; 4],
+    pub uv: [f32; 2],
+    pub outline_color: [f32; 4],
+    pub glow_color: [f32; 4],
+    /// Outline width and glow radius in distance field units
+    pub params: [f32; 2],
+}
+
+impl AnuraVertex for SdfVertex {
+    fn vertex_layout() -> Vec<VertexType> {
+        vec![
+            VertexType::Float32x3,
+            VertexType::Float32x4,
+            VertexType::Float32x2,
+            VertexType::Float32x4,
+            VertexType::Float32x4,
+            VertexType::Float32x2,
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DefaultMesh<V> {
     pub verts: Vec<V>,
     pub inds: Vec<u16>,
@@ -54,38 +85,12 @@ impl<V: AnuraVertex> AnuraMesh for DefaultMesh<V> {
     }
 }
 
-impl DefaultMesh<DefaultVertex> {
-    pub fn quad(pos: (f32, f32, f32), dim: (f32, f32), uv: (f32, f32, f32, f32), color: (f32, f32, f32, f32)) -> Self {
-        let (x, y, z) = pos;
-        let (w, h) = dim;
-        let (uv_x, uv_y, uv_w, uv_h) = uv;
-        let (r, g, b, a) = color;
-
+impl<V: AnuraVertex> DefaultMesh<V> {
+    /// Mesh without any geometry, used as a starting point for merging
+    pub fn empty(z: f32) -> Self {
         Self {
-
-            verts: vec![
-                DefaultVertex {
-                    pos: [x, y, z],
-                    color: [r, g, b, a],
-                    uv: [uv_x, uv_y],
-                },                
-                DefaultVertex {
-                    pos: [x, y + h, z],
-                    color: [r, g, b, a],
-                    uv: [uv_x, uv_y + uv_h],
-                },
-                DefaultVertex {
-                    pos: [x + w, y + h, z],
-                    color: [r, g, b, a],
-                    uv: [uv_x + uv_w, uv_y + uv_h],
-                },
-                DefaultVertex {
-                    pos: [x + w, y, z],
-                    color: [r, g, b, a],
-                    uv: [uv_x + uv_w, uv_y],
-                },
-            ],
-            inds: vec![0, 1, 2, 2, 3, 0],
+            verts: Vec::new(),
+            inds: Vec::new(),
             could_be_transparent: false,
             highest_z: z,
         }
@@ -122,79 +127,156 @@ impl DefaultMesh<DefaultVertex> {
             self.could_be_transparent = true;
         }
     }
+}
+
+impl DefaultMesh<DefaultVertex> {
+    /// Moves every vertex by `offset`, used to place meshes which were built once and cached
+    pub fn translate(&mut self, offset: (f32, f32, f32)) {
+        for vert in &mut self.verts {
+            vert.pos[0] += offset.0;
+            vert.pos[1] += offset.1;
+            vert.pos[2] += offset.2;
+        }
+
+        self.highest_z += offset.2;
+    }
+
+    pub fn quad(pos: (f32, f32, f32), dim: (f32, f32), uv: (f32, f32, f32, f32), color: (f32, f32, f32, f32)) -> Self {
+        let (x, y, z) = pos;
+        let (w, h) = dim;
+        let (uv_x, uv_y, uv_w, uv_h) = uv;
+        let (r, g, b, a) = color;
+
+        Self {
+
+            verts: vec![
+                DefaultVertex {
+                    pos: [x, y, z],
+                    color: [r, g, b, a],
+                    uv: [uv_x, uv_y],
+                },                
+                DefaultVertex {
+                    pos: [x, y + h, z],
+                    color: [r, g, b, a],
+                    uv: [uv_x, uv_y + uv_h],
+                },
+                DefaultVertex {
+                    pos: [x + w, y + h, z],
+                    color: [r, g, b, a],
+                    uv: [uv_x + uv_w, uv_y + uv_h],
+                },
+                DefaultVertex {
+                    pos: [x + w, y, z],
+                    color: [r, g, b, a],
+                    uv: [uv_x + uv_w, uv_y],
+                },
+            ],
+            inds: vec![0, 1, 2, 2, 3, 0],
+            could_be_transparent: false,
+            highest_z: z,
+        }
+    }
 
     
     pub fn bounded_text<R: AnuraRenderer>(
         initial_pos: (f32, f32, f32),
         bounds: Option<(f32, f32)>,
         text: &str,
-        font: &FontAtlas<R>,
-    ) -> Self {
-        let line_spacing = 5.0;
-
-        let mut pos = (
-            initial_pos.0,
-            initial_pos.1 + font.size, //Set baseline
-            initial_pos.2,
-        );
+        font: &mut FontAtlas<R>,
+    ) -> Vec<(usize, Self)> {
+        let layout = TextLayout::new(font, text, &TextLayoutOptions::bounded(bounds));
 
-        let mut mesh = Self {
-            verts: Vec::new(),
-            inds: Vec::new(),
-            could_be_transparent: true,
-            highest_z: pos.2
-        };
-
-        for c in text.chars() {
-            //Skip newlines and move to new line
-            if c == '\r' || c == '\n' {
-                pos.0 = initial_pos.0;
-                pos.1 += font.size + line_spacing;
-
-                continue;
-            }
+        Self::text_layout(initial_pos, &layout, font, (0.0, 0.0, 0.0, 1.0))
+    }
 
-            let GlyphData { uv, metrics } = match font.glyph_data.get(&c) {
+    /// Builds glyph quads for every visible line of an already laid out text,
+    /// one mesh per atlas page paired with the page index
+    ///
+    /// Glyphs missing from the atlas get rasterized, call `FontAtlas::upload` afterwards.
+    /// Quads are scaled if the text was laid out at a different size than the atlas (sdf fonts)
+    pub fn text_layout<R: AnuraRenderer>(
+        initial_pos: (f32, f32, f32),
+        layout: &TextLayout,
+        font: &mut FontAtlas<R>,
+        color: (f32, f32, f32, f32),
+    ) -> Vec<(usize, Self)> {
+        let mut meshes: Vec<(usize, Self)> = Vec::new();
+        let scale = layout.font_size / font.size;
+
+        for glyph in layout.visible_glyphs() {
+            let GlyphData { uv, metrics, page } = match font.glyph(glyph.face, glyph.glyph_id) {
                 Some(gd) => gd,
                 None => continue,
             };
 
-            let mut dim = (metrics.width as f32, (metrics.height as f32));
+            let dim = (metrics.width as f32 * scale, metrics.height as f32 * scale);
 
-            let mut current_pos = (
-                pos.0 + metrics.xmin as f32,
-                pos.1 - (dim.1 + metrics.ymin as f32),
-                pos.2 + 1.0,
-            );
+            // Whitespace has no bitmap
+            if dim.0 <= 0.0 || dim.1 <= 0.0 { continue }
 
-            //handle overflow
-            if let Some(bounds) = bounds {
-                if current_pos.0 + dim.0 > initial_pos.0 + bounds.0 {
-                    current_pos.1 += font.size + line_spacing;
-                    current_pos.0 = initial_pos.0 + metrics.xmin as f32;
-                    pos.1 += font.size + line_spacing;
-                    pos.0 = current_pos.0;
+            let mesh = match meshes.iter().position(|(p, _)| *p == page) {
+                Some(index) => &mut meshes[index].1,
+                None => {
+                    let mut mesh = Self::empty(initial_pos.2);
+                    mesh.possibly_trasparent();
+                    meshes.push((page, mesh));
+                    &mut meshes.last_mut().unwrap().1
                 }
+            };
 
-                if current_pos.1 + (dim.1 + metrics.ymin as f32) > initial_pos.1 + bounds.1 + metrics.ymin as f32 {
-                    break;
-                }
-            }
-
-            if c == ' ' {
-                dim.0 += metrics.advance_width;
-            }
+            let current_pos = (
+                initial_pos.0 + glyph.x + metrics.xmin as f32 * scale,
+                initial_pos.1 + glyph.y - (dim.1 + metrics.ymin as f32 * scale),
+                initial_pos.2 + 1.0,
+            );
 
             mesh.merge(&mut Self::quad(
                 current_pos,
                 dim,
                 (uv.0, uv.1, uv.2, uv.3),
-                (0.0, 0.0, 0.0, 1.0),
+                color,
             ));
-
-            pos.0 += metrics.advance_width;
         }
 
-        mesh
+        meshes
+    }
+}
+
+impl DefaultMesh<SdfVertex> {
+    /// Same as `DefaultMesh::text_layout` but for sdf atlases, draw the meshes with `SDF_SHADER`
+    pub fn sdf_text_layout<R: AnuraRenderer>(
+        initial_pos: (f32, f32, f32),
+        layout: &TextLayout,
+        font: &mut FontAtlas<R>,
+        color: (f32, f32, f32, f32),
+        effects: &SdfEffects,
+    ) -> Vec<(usize, Self)> {
+        let params = effects.shader_params(layout.font_size);
+        let (or, og, ob, oa) = effects.outline_color;
+        let (gr, gg, gb, ga) = effects.glow_color;
+
+        DefaultMesh::<DefaultVertex>::text_layout(initial_pos, layout, font, color)
+            .into_iter()
+            .map(|(page, mesh)| {
+                let verts = mesh.verts
+                    .iter()
+                    .map(|vert| SdfVertex {
+                        pos: vert.pos,
+                        color: vert.color,
+                        uv: vert.uv,
+                        outline_color: [or, og, ob, oa],
+                        glow_color: [gr, gg, gb, ga],
+                        params,
+                    })
+                    .collect();
+
+                (page, DefaultMesh {
+                    verts,
+                    inds: mesh.inds,
+                    could_be_transparent: true,
+                    highest_z: mesh.highest_z,
+                })
+            })
+            .collect()
     }
 }
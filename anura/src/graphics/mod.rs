@@ -111,7 +111,7 @@ pub trait AnuraRenderer {
 
     fn new_texture(&mut self, data: &[u8], dimensions: (u32, u32), handle: Option<Self::TextureHandle>) -> Self::TextureHandle; 
     fn update_texture(&mut self, texture_handle: Self::TextureHandle, data: &[u8]);
-    fn load_texture(&mut self, texture_path: &Path) -> Self::TextureHandle;
+    fn load_texture(&mut self, texture_path: &Path) -> Result<Self::TextureHandle, AnuraError<Self::ErrorMessage>>;
     fn remove_texture(&mut self, texture_handle: Self::TextureHandle);
 
     fn load_shader(
@@ -120,7 +120,7 @@ pub trait AnuraRenderer {
         vertex_entry: &str,
         fragment_shader: &str,
         fragment_entry: &str,
-    ) -> Self::ShaderHandle;
+    ) -> Result<Self::ShaderHandle, AnuraError<Self::ErrorMessage>>;
     
     fn camera_uniform(&self) -> Self::Uniform;
 }
@@ -131,7 +131,7 @@ pub use wgpu_renderer_glue::*;
 #[cfg(feature = "wgpu-renderer")]
 mod wgpu_renderer_glue {
 
-    use std::{path::PathBuf};
+    use std::{future::Future, path::PathBuf, task::{Context, Poll, Waker}};
 
     use crate::{error::AnuraError};
     use ahash::AHashMap;
@@ -149,6 +149,16 @@ mod wgpu_renderer_glue {
 
     pub struct AnuraWgpuError(wgduck::wgpu::SurfaceError);
 
+    /// Native backends report errors right away, so the scope is ready without running an executor
+    fn pop_error_scope(device: &wgpu::Device) -> Option<wgpu::Error> {
+        let mut future = std::pin::pin!(device.pop_error_scope());
+
+        match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(err) => err,
+            Poll::Pending => None,
+        }
+    }
+
     pub struct WgpuRenderer {
         context: renderer::RenderingContext,
         texture_map: AHashMap<PathBuf, usize>,
@@ -157,8 +167,14 @@ mod wgpu_renderer_glue {
 
     impl WgpuRenderer {
         pub async fn new(window: &Window) -> Self {
-            let context =
-                wgduck::renderer::RenderingContext::new(window.inner_size(), window).await;
+            let size = window.inner_size();
+            let mut context =
+                wgduck::renderer::RenderingContext::new(size, window).await;
+
+            //The gpu only blends in linear space when writing to an srgb surface, the shaders
+            //decode the srgb colours they get so they still come out the same
+            context.config.format = context.config.format.add_srgb_suffix();
+            context.update_surface((size.width, size.height));
 
             Self {
                 context,
@@ -372,11 +388,22 @@ mod wgpu_renderer_glue {
                 (info, mesh)
             }).collect::<Vec<_>>();
 
-            if let Err(wgpu::SurfaceError::Outdated) = self.context.render_batches(
-                merged_batches,
-                distinct_uniforms
-            ) {
-                return Err(AnuraError::FatalError { msg: "surface outdated".to_owned() });
+            //Pipelines are created the first time a shader gets used for a batch
+            self.context.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let result = self.context.render_batches(merged_batches, distinct_uniforms);
+
+            if let Some(err) = pop_error_scope(&self.context.device) {
+                return Err(AnuraError::ShaderCompile { msg: err.to_string() });
+            }
+
+            match result {
+                Err(wgpu::SurfaceError::Outdated) => {
+                    return Err(AnuraError::SurfaceLost { msg: "surface outdated".to_owned() });
+                }
+                Err(wgpu::SurfaceError::Lost) => {
+                    return Err(AnuraError::SurfaceLost { msg: "surface lost".to_owned() });
+                }
+                _ => {}
             }
 
             Ok(())
@@ -436,13 +463,22 @@ mod wgpu_renderer_glue {
             }
         }
 
-        fn load_texture(&mut self, texture_path: &std::path::Path) -> Self::TextureHandle {
+        fn load_texture(&mut self, texture_path: &std::path::Path) -> Result<Self::TextureHandle, AnuraError<Self::ErrorMessage>> {
             match self.texture_map.get(&texture_path.to_path_buf()) {
-                Some(index) => *index,
+                Some(index) => Ok(*index),
                 None => {
 
-                    let image = image::open(texture_path).unwrap();
-    
+                    let image = image::open(texture_path).map_err(|err| match err {
+                        image::ImageError::IoError(err) => AnuraError::AssetNotFound {
+                            asset: texture_path.display().to_string(),
+                            source: Some(err),
+                        },
+                        err => AnuraError::DecodeFailure {
+                            asset: texture_path.display().to_string(),
+                            source: Some(err.into()),
+                        },
+                    })?;
+
                     let texture = Texture::new(
                         &self.context.device, 
                         &self.context.queue, 
@@ -455,7 +491,7 @@ mod wgpu_renderer_glue {
 
                     self.texture_map.insert(texture_path.to_path_buf(), index);
 
-                    index
+                    Ok(index)
                 }
             }
         }
@@ -479,20 +515,26 @@ mod wgpu_renderer_glue {
             vertex_entry: &str,
             fragment_shader: &str,
             fragment_entry: &str,
-        ) -> Self::ShaderHandle {
+        ) -> Result<Self::ShaderHandle, AnuraError<Self::ErrorMessage>> {
+            //Validation errors would otherwise end up in wgpu's uncaptured error handler
+            self.context.device.push_error_scope(wgpu::ErrorFilter::Validation);
             self.context
                 .create_shader_module_if_doesnt_exist(vertex_shader);
             self.context
                 .create_shader_module_if_doesnt_exist(fragment_shader);
 
+            if let Some(err) = pop_error_scope(&self.context.device) {
+                return Err(AnuraError::ShaderCompile { msg: err.to_string() });
+            }
+
             //Probably not too efficient considering all these copies for every
             //submitted render command with this shader
-             Shader::new(
+            Ok(Shader::new(
                 vertex_shader,
                 vertex_entry.to_owned(),
                 fragment_shader,
                 fragment_entry.to_owned(),
-            )
+            ))
         }
 
         fn camera_uniform(&self) -> Self::Uniform {
@@ -1,22 +1,121 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, MouseButton, VirtualKeyCode},
+    event::{ElementState, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase, VirtualKeyCode},
 };
 
-#[derive(Debug, Default, Clone)]
+use crate::gesture::{Gesture, GestureRecognizer};
+
+/// How far one notch of a mouse wheel scrolls
+const PIXELS_PER_LINE: f32 = 40.0;
+/// Clicks closer together than this count as double/triple clicks
+const MULTI_CLICK_TIME: Duration = Duration::from_millis(400);
+/// How far the mouse can move in between clicks of a double click
+const MULTI_CLICK_DISTANCE: f64 = 4.0;
+
+#[derive(Debug, Clone)]
 pub struct KeyPressState {
     frame_count: u32,
     was_just_released: bool,
+    /// See `InputManager::now`
+    pressed_at: Duration,
 }
 
-#[derive(Debug, Default, Clone)]
+impl Default for KeyPressState {
+    fn default() -> Self {
+        Self {
+            frame_count: 1,
+            was_just_released: false,
+            pressed_at: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MouseButtonPressState {
     frame_count: u32,
     was_just_released: bool,
-
-    #[allow(dead_code)]
+    pressed_at: Duration,
     starting_position: PhysicalPosition<f64>,
+    /// 1 for single clicks, 2 for double clicks and 3 for triple clicks
+    click_count: u32,
+}
+
+impl Default for MouseButtonPressState {
+    fn default() -> Self {
+        Self {
+            frame_count: 1,
+            was_just_released: false,
+            pressed_at: Duration::ZERO,
+            starting_position: PhysicalPosition::default(),
+            click_count: 1,
+        }
+    }
+}
+
+/// A finger on a touchscreen
+#[derive(Debug, Clone)]
+pub struct TouchPoint {
+    /// Stays the same until the finger is lifted
+    pub id: u64,
+    pub position: PhysicalPosition<f64>,
+    pub start_position: PhysicalPosition<f64>,
+    /// See `InputManager::now`
+    pub started_at: Duration,
+    /// When it last moved
+    pub updated_at: Duration,
+    /// Pixels per second, smoothed over the last few moves
+    pub velocity: (f32, f32),
+}
+
+impl TouchPoint {
+    pub(crate) fn location(&self) -> (f32, f32) {
+        (self.position.x as f32, self.position.y as f32)
+    }
+
+    pub(crate) fn start(&self) -> (f32, f32) {
+        (self.start_position.x as f32, self.start_position.y as f32)
+    }
+
+    fn move_to(&mut self, position: PhysicalPosition<f64>, now: Duration) {
+        let elapsed = now.saturating_sub(self.updated_at).as_secs_f32();
+
+        if elapsed > 0.0 {
+            let speed = (
+                (position.x - self.position.x) as f32 / elapsed,
+                (position.y - self.position.y) as f32 / elapsed,
+            );
+            self.velocity = (
+                (self.velocity.0 + speed.0) / 2.0,
+                (self.velocity.1 + speed.1) / 2.0,
+            );
+        }
+
+        self.position = position;
+        self.updated_at = now;
+    }
+}
+
+/// Either a key or a mouse button, lets the queries below take both
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputButton {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+impl From<VirtualKeyCode> for InputButton {
+    fn from(key: VirtualKeyCode) -> Self {
+        InputButton::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputButton {
+    fn from(button: MouseButton) -> Self {
+        InputButton::Mouse(button)
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -24,11 +123,64 @@ pub  struct InputManager {
     pub keys: HashMap<VirtualKeyCode, KeyPressState>,
     pub mouse_buttons: HashMap<MouseButton, MouseButtonPressState>,
     pub mouse_position: PhysicalPosition<f64>,
-    pub last_received_char: Option<char>,
+    /// How far the mouse moved since the last frame in pixels
+    pub mouse_delta: (f32, f32),
+    /// Shift, ctrl, alt and super (`logo`) as reported by the os
+    pub modifiers: ModifiersState,
+    /// Every character typed since the last frame, in order
+    pub received_chars: Vec<char>,
+    /// Every key press since the last frame including key repeats, in order
+    pub key_presses: Vec<VirtualKeyCode>,
+    /// How far the mouse wheel/touchpad scrolled since the last frame in pixels,
+    /// positive y is scrolling up
+    pub scroll_delta: (f32, f32),
+    /// Fingers currently on the touchscreen
+    pub touches: HashMap<u64, TouchPoint>,
+    /// Gestures recognized since the last frame
+    pub gestures: Vec<Gesture>,
+    /// The touch passed off as the left mouse button, only while it's the only one
+    pointer_touch: Option<u64>,
+    recognizer: GestureRecognizer,
+    /// When and where every button was last clicked and how many clicks in a row that was
+    last_clicks: HashMap<MouseButton, (Duration, PhysicalPosition<f64>, u32)>,
+    /// Wall clock time `now` counts from, set by the first `tick`
+    clock_start: Option<Instant>,
+    now: Duration,
 }
 
 impl InputManager {
+    /// Time as far as input is concerned. Double clicks, gestures, action sequences and
+    /// animations go by this instead of the wall clock, so replays can set it to the
+    /// recorded times and play out the same
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// Moves the clock to the current time, done by the app for every event and frame
+    /// unless it's replaying
+    pub fn tick(&mut self) {
+        let start = *self.clock_start.get_or_insert_with(Instant::now);
+        self.set_time(start.elapsed());
+    }
+
+    /// Moves the clock to `time`, it never goes backwards
+    pub fn set_time(&mut self, time: Duration) {
+        self.now = self.now.max(time);
+    }
+
+    /// Forgets all input but keeps the clock running, see `AnuraApp::replay`
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            clock_start: self.clock_start,
+            now: self.now,
+            ..Default::default()
+        };
+    }
+
     pub fn update_mouse_pos(&mut self, position: &PhysicalPosition<f64>) {
+        self.mouse_delta.0 += (position.x - self.mouse_position.x) as f32;
+        self.mouse_delta.1 += (position.y - self.mouse_position.y) as f32;
+
         self.mouse_position = *position;
     }
 
@@ -41,32 +193,231 @@ impl InputManager {
                 mb_state
             }
             None => MouseButtonPressState {
-                frame_count: 1,
                 was_just_released: was_released,
+                pressed_at: self.now,
                 starting_position: self.mouse_position,
+                click_count: self.register_click(*button),
+                ..Default::default()
             },
         };
 
         self.mouse_buttons.insert(*button, mouse_button_state);
     }
 
+    /// Counts a click, returns 1 for single, 2 for double and 3 for triple clicks
+    fn register_click(&mut self, button: MouseButton) -> u32 {
+        let now = self.now;
+        let position = self.mouse_position;
+
+        let count = match self.last_clicks.get(&button) {
+            Some((time, last, count)) if now.saturating_sub(*time) <= MULTI_CLICK_TIME
+                && (position.x - last.x).abs() <= MULTI_CLICK_DISTANCE
+                && (position.y - last.y).abs() <= MULTI_CLICK_DISTANCE => {
+                // A fourth click starts over
+                count % 3 + 1
+            }
+            _ => 1,
+        };
+
+        self.last_clicks.insert(button, (now, position, count));
+        count
+    }
+
     pub fn update_key(&mut self, keycode: &VirtualKeyCode, state: &ElementState) {
         let was_released = *state == ElementState::Released;
 
+        if !was_released {
+            self.key_presses.push(*keycode);
+        }
+
         let key_state = match self.keys.remove(keycode) {
             Some(mut key_state) => {
                 key_state.was_just_released = was_released;
                 key_state
             }
             None => KeyPressState {
-                frame_count: 1,
                 was_just_released: was_released,
+                pressed_at: self.now,
+                ..Default::default()
             },
         };
 
         self.keys.insert(*keycode, key_state);
     }
 
+    pub fn update_touch(&mut self, id: u64, phase: TouchPhase, position: &PhysicalPosition<f64>) {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.insert(id, TouchPoint {
+                    id,
+                    position: *position,
+                    start_position: *position,
+                    started_at: self.now,
+                    updated_at: self.now,
+                    velocity: (0.0, 0.0),
+                });
+                self.recognizer.touch_started(id, &self.touches, &mut self.gestures);
+
+                if self.touches.len() == 1 && !self.is_down(MouseButton::Left) {
+                    self.pointer_touch = Some(id);
+                    self.update_mouse_pos(position);
+                    self.update_mouse_button(&ElementState::Pressed, &MouseButton::Left);
+                } else if self.pointer_touch.take().is_some() {
+                    // More fingers make it a pinch and not a click
+                    self.update_mouse_button(&ElementState::Released, &MouseButton::Left);
+                }
+            }
+            TouchPhase::Moved => {
+                if let Some(point) = self.touches.get_mut(&id) {
+                    point.move_to(*position, self.now);
+                }
+
+                if self.pointer_touch == Some(id) {
+                    self.update_mouse_pos(position);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let mut point = match self.touches.remove(&id) {
+                    Some(point) => point,
+                    None => return,
+                };
+                if point.position != *position {
+                    point.move_to(*position, self.now);
+                }
+
+                let cancelled = phase == TouchPhase::Cancelled;
+                self.recognizer.touch_ended(&point, cancelled, self.now, &self.touches, &mut self.gestures);
+
+                if self.pointer_touch == Some(id) {
+                    self.pointer_touch = None;
+                    self.update_mouse_pos(position);
+                    self.update_mouse_button(&ElementState::Released, &MouseButton::Left);
+                }
+            }
+        }
+    }
+
+    /// Recognizes gestures which depend on time passing, like long presses, called once a frame
+    pub fn update_gestures(&mut self) {
+        self.recognizer.update(self.now, &self.touches, &mut self.gestures);
+    }
+
+    pub fn update_modifiers(&mut self, modifiers: &ModifiersState) {
+        self.modifiers = *modifiers;
+    }
+
+    pub fn update_scroll(&mut self, delta: &MouseScrollDelta) {
+        let (x, y) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (x * PIXELS_PER_LINE, y * PIXELS_PER_LINE),
+            MouseScrollDelta::PixelDelta(position) => (position.x as f32, position.y as f32),
+        };
+
+        self.scroll_delta.0 += x;
+        self.scroll_delta.1 += y;
+    }
+
+    pub fn receive_char(&mut self, character: char) {
+        self.received_chars.push(character);
+    }
+
+    /// The last character typed this frame
+    #[deprecated(note = "more than one character can be typed per frame, use `received_chars`")]
+    pub fn last_received_char(&self) -> Option<char> {
+        self.received_chars.last().copied()
+    }
+
+    /// Frame count, whether it was released and when it went down
+    fn press_state(&self, button: InputButton) -> Option<(u32, bool, Duration)> {
+        match button {
+            InputButton::Key(key) => self.keys
+                .get(&key)
+                .map(|state| (state.frame_count, state.was_just_released, state.pressed_at)),
+            InputButton::Mouse(button) => self.mouse_buttons
+                .get(&button)
+                .map(|state| (state.frame_count, state.was_just_released, state.pressed_at)),
+        }
+    }
+
+    pub fn is_down(&self, button: impl Into<InputButton>) -> bool {
+        self.press_state(button.into()).is_some_and(|(_, released, _)| !released)
+    }
+
+    /// True during the frame the key/button went down, even if it was let go in the same frame
+    pub fn just_pressed(&self, button: impl Into<InputButton>) -> bool {
+        self.press_state(button.into()).is_some_and(|(frames, _, _)| frames == 1)
+    }
+
+    /// True during the frame the key/button was let go
+    pub fn just_released(&self, button: impl Into<InputButton>) -> bool {
+        self.press_state(button.into()).is_some_and(|(_, released, _)| released)
+    }
+
+    /// Number of frames the key/button has been down for, 0 if it isn't
+    pub fn held_frames(&self, button: impl Into<InputButton>) -> u32 {
+        match self.press_state(button.into()) {
+            Some((frames, false, _)) => frames,
+            _ => 0,
+        }
+    }
+
+    /// How long the key/button has been down for, zero if it isn't
+    pub fn held_for(&self, button: impl Into<InputButton>) -> Duration {
+        match self.press_state(button.into()) {
+            Some((_, false, pressed_at)) => self.now.saturating_sub(pressed_at),
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub fn shift(&self) -> bool {
+        self.modifiers.shift()
+    }
+
+    pub fn ctrl(&self) -> bool {
+        self.modifiers.ctrl()
+    }
+
+    pub fn alt(&self) -> bool {
+        self.modifiers.alt()
+    }
+
+    /// The windows key or cmd on macos
+    pub fn super_key(&self) -> bool {
+        self.modifiers.logo()
+    }
+
+    /// The modifier of shortcuts like copy and paste, cmd on macos and ctrl everywhere else
+    pub fn command(&self) -> bool {
+        match cfg!(target_os = "macos") {
+            true => self.modifiers.logo(),
+            false => self.modifiers.ctrl(),
+        }
+    }
+
+    /// How far the mouse moved since the button went down, None if it isn't down.
+    /// Still available in the frame the button is released
+    pub fn drag_distance(&self, button: MouseButton) -> Option<(f32, f32)> {
+        self.mouse_buttons.get(&button).map(|state| (
+            (self.mouse_position.x - state.starting_position.x) as f32,
+            (self.mouse_position.y - state.starting_position.y) as f32,
+        ))
+    }
+
+    /// 1 for a single click, 2 for a double click and 3 for a triple click,
+    /// 0 if the button isn't down
+    pub fn click_count(&self, button: MouseButton) -> u32 {
+        self.mouse_buttons.get(&button).map_or(0, |state| state.click_count)
+    }
+
+    /// True in the frame the second click of a double click went down
+    pub fn double_clicked(&self, button: MouseButton) -> bool {
+        self.just_pressed(button) && self.click_count(button) == 2
+    }
+
+    /// True in the frame the third click of a triple click went down
+    pub fn triple_clicked(&self, button: MouseButton) -> bool {
+        self.just_pressed(button) && self.click_count(button) == 3
+    }
+
     pub fn update_inputs(&mut self) {
         self.keys.iter_mut().for_each(|(_, key_state)| {
             if !key_state.was_just_released {
@@ -80,7 +431,11 @@ impl InputManager {
             }
         });
 
-        self.last_received_char = None;
+        self.received_chars.clear();
+        self.key_presses.clear();
+        self.scroll_delta = (0.0, 0.0);
+        self.mouse_delta = (0.0, 0.0);
+        self.gestures.clear();
 
         self.keys
             .retain(|_, key_state| !key_state.was_just_released);
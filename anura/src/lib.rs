@@ -3,27 +3,41 @@
 #![feature(trait_upcasting)]
 #[macro_use]
 
+pub mod action;
+pub mod animation;
 pub mod app;
 pub mod arena;
+pub mod clipboard;
 pub mod context;
+pub mod drag;
+pub mod gesture;
 pub mod error;
 pub mod event;
 pub mod graphics;
 pub mod handle;
 pub mod input;
+pub mod replay;
+pub mod task;
 pub mod uitree;
 pub mod view;
 pub mod widget;
 pub mod font;
 
 pub mod prelude {
+    pub use super::action::*;
+    pub use super::animation::*;
     pub use super::app::*;
+    pub use super::clipboard::*;
     pub use super::context::*;
+    pub use super::drag::*;
+    pub use super::gesture::*;
     pub use super::error::*;
     pub use super::event::*;
     pub use super::graphics::*;
     pub use super::handle::*;
     pub use super::input::*;
+    pub use super::replay::*;
+    pub use super::task::*;
     pub use super::uitree::*;
     pub use super::view::*;
     pub use super::widget::*;
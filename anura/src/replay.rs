@@ -0,0 +1,603 @@
+use std::{
+    fmt,
+    fs,
+    io,
+    path::Path,
+    time::Duration,
+};
+
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, Ime, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode, WindowEvent},
+};
+
+use crate::{error::AnuraError, input::InputManager};
+
+/// First line of every recording file, bumped if the format ever changes
+const HEADER: &str = "anura-input 1";
+
+/// The part of a winit window event that ends up in `InputManager` (or the ime queue)
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    Key { key: VirtualKeyCode, state: ElementState },
+    Char(char),
+    Modifiers(ModifiersState),
+    Ime(Ime),
+    MouseMoved(PhysicalPosition<f64>),
+    MouseButton { button: MouseButton, state: ElementState },
+    Scroll(MouseScrollDelta),
+    Touch { id: u64, phase: TouchPhase, position: PhysicalPosition<f64> },
+}
+
+impl InputEvent {
+    /// None for window events which aren't input, like resizing
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput { state, virtual_keycode: Some(key), .. },
+                ..
+            } => InputEvent::Key { key: *key, state: *state },
+            WindowEvent::ReceivedCharacter(character) => InputEvent::Char(*character),
+            WindowEvent::ModifiersChanged(modifiers) => InputEvent::Modifiers(*modifiers),
+            WindowEvent::Ime(ime) => InputEvent::Ime(ime.clone()),
+            WindowEvent::CursorMoved { position, .. } => InputEvent::MouseMoved(*position),
+            WindowEvent::MouseInput { state, button, .. } => InputEvent::MouseButton { button: *button, state: *state },
+            WindowEvent::MouseWheel { delta, .. } => InputEvent::Scroll(*delta),
+            WindowEvent::Touch(Touch { id, phase, location, .. }) => InputEvent::Touch { id: *id, phase: *phase, position: *location },
+            _ => return None,
+        })
+    }
+
+    /// Feeds the event to the input manager, ime events are left to the caller
+    pub fn apply(&self, input: &mut InputManager) {
+        match self {
+            InputEvent::Key { key, state } => input.update_key(key, state),
+            InputEvent::Char(character) => input.receive_char(*character),
+            InputEvent::Modifiers(modifiers) => input.update_modifiers(modifiers),
+            InputEvent::MouseMoved(position) => input.update_mouse_pos(position),
+            InputEvent::MouseButton { button, state } => input.update_mouse_button(state, button),
+            InputEvent::Scroll(delta) => input.update_scroll(delta),
+            InputEvent::Touch { id, phase, position } => input.update_touch(*id, *phase, position),
+            InputEvent::Ime(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// Number of updates since the recording started, the event came in before that update
+    pub frame: u64,
+    /// Time since the recording started
+    pub time: Duration,
+    pub event: InputEvent,
+}
+
+/// Input of a session which can be saved to a file and replayed later
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputRecording {
+    /// Inner size of the window when the recording started
+    pub window_size: (u32, u32),
+    /// Number of frames recorded, can be more than the frame of the last event
+    pub frames: u64,
+    pub events: Vec<RecordedEvent>,
+    /// Time since the recording started at every frame's update, so time passes
+    /// during a replay even in frames without events
+    pub frame_times: Vec<Duration>,
+}
+
+/// A line of a recording which couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingParseError {
+    /// Starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RecordingParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RecordingParseError {}
+
+impl InputRecording {
+    /// Text version of the recording, one event per line
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\nsize {} {}\nframes {}\n", self.window_size.0, self.window_size.1, self.frames);
+
+        let mut updates = self.frame_times.iter().enumerate().peekable();
+
+        for RecordedEvent { frame, time, event } in &self.events {
+            // Events come in before the update of their frame
+            while let Some((update, update_time)) = updates.next_if(|(update, _)| (*update as u64) < *frame) {
+                text.push_str(&format!("{update} {} update\n", update_time.as_micros()));
+            }
+
+            let event = match event {
+                InputEvent::Key { key, state } => format!("key {key:?} {}", state_name(*state)),
+                InputEvent::Char(character) => format!("char {}", *character as u32),
+                InputEvent::Modifiers(modifiers) => format!("modifiers {}", modifiers.bits()),
+                InputEvent::Ime(Ime::Enabled) => "ime enabled".to_owned(),
+                InputEvent::Ime(Ime::Disabled) => "ime disabled".to_owned(),
+                InputEvent::Ime(Ime::Preedit(text, cursor)) => match cursor {
+                    Some((start, end)) => format!("preedit {start} {end} {text:?}"),
+                    None => format!("preedit - - {text:?}"),
+                },
+                InputEvent::Ime(Ime::Commit(text)) => format!("commit {text:?}"),
+                InputEvent::MouseMoved(position) => format!("move {} {}", position.x, position.y),
+                InputEvent::MouseButton { button, state } => {
+                    let button = match button {
+                        MouseButton::Left => "left".to_owned(),
+                        MouseButton::Right => "right".to_owned(),
+                        MouseButton::Middle => "middle".to_owned(),
+                        MouseButton::Other(other) => other.to_string(),
+                    };
+                    format!("button {button} {}", state_name(*state))
+                }
+                InputEvent::Scroll(MouseScrollDelta::LineDelta(x, y)) => format!("scroll lines {x} {y}"),
+                InputEvent::Scroll(MouseScrollDelta::PixelDelta(delta)) => format!("scroll pixels {} {}", delta.x, delta.y),
+                InputEvent::Touch { id, phase, position } => {
+                    let phase = match phase {
+                        TouchPhase::Started => "started",
+                        TouchPhase::Moved => "moved",
+                        TouchPhase::Ended => "ended",
+                        TouchPhase::Cancelled => "cancelled",
+                    };
+                    format!("touch {id} {phase} {} {}", position.x, position.y)
+                }
+            };
+
+            text.push_str(&format!("{frame} {} {event}\n", time.as_micros()));
+        }
+
+        for (update, update_time) in updates {
+            text.push_str(&format!("{update} {} update\n", update_time.as_micros()));
+        }
+
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, RecordingParseError> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == HEADER => {}
+            _ => return Err(RecordingParseError { line: 1, message: format!("expected `{HEADER}`") }),
+        }
+
+        let mut recording = InputRecording::default();
+
+        for (i, line) in lines {
+            let error = |message: &str| RecordingParseError { line: i + 1, message: format!("{message} in `{line}`") };
+            let mut parts = line.split_whitespace();
+
+            let first = parts.next().unwrap_or_default();
+            match first {
+                "size" => {
+                    recording.window_size = (parse(parts.next(), &error)?, parse(parts.next(), &error)?);
+                    continue;
+                }
+                "frames" => {
+                    recording.frames = parse(parts.next(), &error)?;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let frame = parse(Some(first), &error)?;
+            let time = Duration::from_micros(parse(parts.next(), &error)?);
+
+            let event = match parts.next() {
+                Some("update") if frame as usize == recording.frame_times.len() => {
+                    recording.frames = recording.frames.max(frame + 1);
+                    recording.frame_times.push(time);
+                    continue;
+                }
+                Some("update") => return Err(error("update out of order")),
+                Some("key") => InputEvent::Key {
+                    key: parts.next().and_then(key_from_name).ok_or_else(|| error("unknown key"))?,
+                    state: parse_state(parts.next()).ok_or_else(|| error("expected down or up"))?,
+                },
+                Some("char") => InputEvent::Char(
+                    char::from_u32(parse(parts.next(), &error)?).ok_or_else(|| error("invalid char"))?,
+                ),
+                Some("modifiers") => InputEvent::Modifiers(ModifiersState::from_bits_truncate(parse(parts.next(), &error)?)),
+                Some("ime") => match parts.next() {
+                    Some("enabled") => InputEvent::Ime(Ime::Enabled),
+                    Some("disabled") => InputEvent::Ime(Ime::Disabled),
+                    _ => return Err(error("expected enabled or disabled")),
+                },
+                Some("preedit") => {
+                    let cursor = match (parts.next(), parts.next()) {
+                        (Some("-"), Some("-")) => None,
+                        (start, end) => Some((parse(start, &error)?, parse(end, &error)?)),
+                    };
+                    let text = quoted_rest(line, 5).ok_or_else(|| error("expected quoted text"))?;
+                    InputEvent::Ime(Ime::Preedit(text, cursor))
+                }
+                Some("commit") => {
+                    let text = quoted_rest(line, 3).ok_or_else(|| error("expected quoted text"))?;
+                    InputEvent::Ime(Ime::Commit(text))
+                }
+                Some("move") => InputEvent::MouseMoved(PhysicalPosition::new(
+                    parse(parts.next(), &error)?,
+                    parse(parts.next(), &error)?,
+                )),
+                Some("button") => {
+                    let button = match parts.next() {
+                        Some("left") => MouseButton::Left,
+                        Some("right") => MouseButton::Right,
+                        Some("middle") => MouseButton::Middle,
+                        other => MouseButton::Other(parse(other, &error)?),
+                    };
+                    let state = parse_state(parts.next()).ok_or_else(|| error("expected down or up"))?;
+                    InputEvent::MouseButton { button, state }
+                }
+                Some("scroll") => {
+                    let kind = parts.next();
+                    let (x, y) = (parse(parts.next(), &error)?, parse(parts.next(), &error)?);
+
+                    match kind {
+                        Some("lines") => InputEvent::Scroll(MouseScrollDelta::LineDelta(x as f32, y as f32)),
+                        Some("pixels") => InputEvent::Scroll(MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y))),
+                        _ => return Err(error("expected lines or pixels")),
+                    }
+                }
+                Some("touch") => {
+                    let id = parse(parts.next(), &error)?;
+                    let phase = match parts.next() {
+                        Some("started") => TouchPhase::Started,
+                        Some("moved") => TouchPhase::Moved,
+                        Some("ended") => TouchPhase::Ended,
+                        Some("cancelled") => TouchPhase::Cancelled,
+                        _ => return Err(error("expected a touch phase")),
+                    };
+                    let position = PhysicalPosition::new(parse(parts.next(), &error)?, parse(parts.next(), &error)?);
+                    InputEvent::Touch { id, phase, position }
+                }
+                _ => return Err(error("unknown event")),
+            };
+
+            recording.frames = recording.frames.max(frame + 1);
+            recording.events.push(RecordedEvent { frame, time, event });
+        }
+
+        Ok(recording)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load<T>(path: impl AsRef<Path>) -> Result<Self, AnuraError<T>> {
+        let path = path.as_ref();
+
+        let text = fs::read_to_string(path).map_err(|err| AnuraError::AssetNotFound {
+            asset: path.display().to_string(),
+            source: Some(err),
+        })?;
+
+        Self::from_text(&text).map_err(|err| AnuraError::DecodeFailure {
+            asset: path.display().to_string(),
+            source: Some(Box::new(err)),
+        })
+    }
+}
+
+fn state_name(state: ElementState) -> &'static str {
+    match state {
+        ElementState::Pressed => "down",
+        ElementState::Released => "up",
+    }
+}
+
+fn parse_state(part: Option<&str>) -> Option<ElementState> {
+    match part {
+        Some("down") => Some(ElementState::Pressed),
+        Some("up") => Some(ElementState::Released),
+        _ => None,
+    }
+}
+
+fn parse<T: std::str::FromStr>(
+    part: Option<&str>,
+    error: &impl Fn(&str) -> RecordingParseError,
+) -> Result<T, RecordingParseError> {
+    part.and_then(|part| part.parse().ok()).ok_or_else(|| error("expected a number"))
+}
+
+/// The quoted string after the first `skip` words of the line, undoing `{:?}`
+fn quoted_rest(line: &str, skip: usize) -> Option<String> {
+    let mut rest = line.trim_start();
+    for _ in 0..skip {
+        rest = rest.split_once(char::is_whitespace)?.1.trim_start();
+    }
+
+    let mut chars = rest.trim_end().strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        text.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                let hex = chars.by_ref().skip(1).take_while(|c| *c != '}').collect::<String>();
+                char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            other => other,
+        });
+    }
+
+    Some(text)
+}
+
+/// Collects the input seen by the app, see `AnuraApp::start_recording`
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    recording: InputRecording,
+    /// Clock of the input manager when the recording started
+    start: Duration,
+}
+
+impl InputRecorder {
+    /// `now` is `InputManager::now`, as are the times passed to the other methods
+    pub fn new(window_size: (u32, u32), now: Duration) -> Self {
+        Self {
+            recording: InputRecording { window_size, ..Default::default() },
+            start: now,
+        }
+    }
+
+    pub fn record(&mut self, event: InputEvent, now: Duration) {
+        self.recording.events.push(RecordedEvent {
+            frame: self.recording.frames,
+            time: now.saturating_sub(self.start),
+            event,
+        });
+    }
+
+    /// Called after every update with the time it ran at, later events belong to the next frame
+    pub fn next_frame(&mut self, now: Duration) {
+        self.recording.frame_times.push(now.saturating_sub(self.start));
+        self.recording.frames += 1;
+    }
+
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+/// Plays a recording back one frame at a time, moving the clock of the input manager
+/// along with the recorded times
+///
+/// Without a window it can drive an `InputManager` directly, e.g. for tests:
+/// ```ignore
+/// let mut replayer = InputReplayer::new(InputRecording::load::<()>("bug.input")?);
+/// let mut input = InputManager::default();
+///
+/// while replayer.step(&mut input) {
+///     // whatever looks at the input
+///     input.update_inputs();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputReplayer {
+    recording: InputRecording,
+    /// Index of the next event to play
+    position: usize,
+    frame: u64,
+    /// Clock of the input manager when playback started, the recorded times count from there
+    start: Option<Duration>,
+}
+
+impl InputReplayer {
+    pub fn new(recording: InputRecording) -> Self {
+        Self { recording, position: 0, frame: 0, start: None }
+    }
+
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames && self.position >= self.recording.events.len()
+    }
+
+    /// Events of the next frame, empty once the recording is over
+    pub fn next_frame(&mut self) -> &[RecordedEvent] {
+        let start = self.position;
+
+        while self.recording.events.get(self.position).is_some_and(|event| event.frame <= self.frame) {
+            self.position += 1;
+        }
+        self.frame += 1;
+
+        &self.recording.events[start..self.position]
+    }
+
+    /// Plays the next frame into the input manager, returns false once the recording is over
+    pub fn step(&mut self, input: &mut InputManager) -> bool {
+        self.play_frame(input, |input, event| event.apply(input))
+    }
+
+    /// Like `step`, but every event goes to `handle` which passes it on to the input manager
+    pub fn play_frame(&mut self, input: &mut InputManager, mut handle: impl FnMut(&mut InputManager, &InputEvent)) -> bool {
+        if self.is_finished() { return false }
+
+        let start = *self.start.get_or_insert(input.now());
+        let frame = self.frame as usize;
+
+        for recorded in self.next_frame() {
+            input.set_time(start + recorded.time);
+            handle(input, &recorded.event);
+        }
+
+        if let Some(time) = self.recording.frame_times.get(frame) {
+            input.set_time(start + *time);
+        }
+
+        true
+    }
+}
+
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        /// Inverse of `VirtualKeyCode`'s `Debug`
+        fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_names!(
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+    Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down, Back, Return, Space, Compose, Caret, Numlock,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    NumpadAdd, NumpadDivide, NumpadDecimal, NumpadComma, NumpadEnter, NumpadEquals,
+    NumpadMultiply, NumpadSubtract,
+    AbntC1, AbntC2, Apostrophe, Apps, Asterisk, At, Ax, Backslash, Calculator, Capital,
+    Colon, Comma, Convert, Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl, LShift,
+    LWin, Mail, MediaSelect, MediaStop, Minus, Mute, MyComputer, NavigateForward,
+    NavigateBackward, NextTrack, NoConvert, OEM102, Period, PlayPause, Plus, Power,
+    PrevTrack, RAlt, RBracket, RControl, RShift, RWin, Semicolon, Slash, Sleep, Stop,
+    Sysrq, Tab, Underline, Unlabeled, VolumeDown, VolumeUp, Wake, WebBack, WebFavorites,
+    WebForward, WebHome, WebRefresh, WebSearch, WebStop, Yen, Copy, Paste, Cut,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut recorder = InputRecorder::new((800, 600), ms(0));
+
+        recorder.record(InputEvent::Key { key: VirtualKeyCode::A, state: ElementState::Pressed }, ms(1));
+        recorder.record(InputEvent::Char('é'), ms(2));
+        recorder.record(InputEvent::Modifiers(ModifiersState::CTRL | ModifiersState::SHIFT), ms(3));
+        recorder.next_frame(ms(16));
+
+        recorder.record(InputEvent::Ime(Ime::Enabled), ms(17));
+        recorder.record(InputEvent::Ime(Ime::Preedit("say \"hi\"\t \n".to_owned(), Some((1, 3)))), ms(18));
+        recorder.record(InputEvent::Ime(Ime::Preedit(String::new(), None)), ms(19));
+        recorder.record(InputEvent::Ime(Ime::Commit("  \u{200b}日本\\ ".to_owned())), ms(20));
+        recorder.record(InputEvent::Ime(Ime::Disabled), ms(21));
+        recorder.next_frame(ms(33));
+        recorder.next_frame(ms(50));
+
+        recorder.record(InputEvent::MouseMoved(PhysicalPosition::new(1.5, -2.25)), ms(51));
+        recorder.record(InputEvent::MouseButton { button: MouseButton::Other(7), state: ElementState::Pressed }, ms(52));
+        recorder.record(InputEvent::MouseButton { button: MouseButton::Left, state: ElementState::Released }, ms(53));
+        recorder.record(InputEvent::Scroll(MouseScrollDelta::LineDelta(0.5, -1.0)), ms(54));
+        recorder.record(InputEvent::Scroll(MouseScrollDelta::PixelDelta(PhysicalPosition::new(3.0, 4.5))), ms(55));
+        recorder.record(InputEvent::Touch { id: 2, phase: TouchPhase::Cancelled, position: PhysicalPosition::new(10.0, 20.0) }, ms(56));
+        recorder.next_frame(ms(66));
+
+        let recording = recorder.finish();
+        let parsed = InputRecording::from_text(&recording.to_text()).unwrap();
+
+        assert_eq!(parsed, recording);
+        assert_eq!(parsed.frames, 4);
+    }
+
+    #[test]
+    fn bad_lines_are_reported() {
+        let error = InputRecording::from_text("anura-input 1\nsize 800 600\n0 0 key NotAKey down\n").unwrap_err();
+        assert_eq!(error.line, 3);
+
+        assert!(InputRecording::from_text("something else").is_err());
+    }
+
+    #[test]
+    fn replayer_drives_input_manager() {
+        let mut recorder = InputRecorder::new((800, 600), ms(0));
+
+        recorder.record(InputEvent::Key { key: VirtualKeyCode::A, state: ElementState::Pressed }, ms(5));
+        recorder.next_frame(ms(10));
+        // A is held through a frame without events
+        recorder.next_frame(ms(20));
+        recorder.record(InputEvent::Key { key: VirtualKeyCode::A, state: ElementState::Released }, ms(25));
+        recorder.record(InputEvent::Key { key: VirtualKeyCode::B, state: ElementState::Pressed }, ms(26));
+        recorder.record(InputEvent::Key { key: VirtualKeyCode::B, state: ElementState::Released }, ms(27));
+        recorder.next_frame(ms(30));
+
+        let mut replayer = InputReplayer::new(recorder.finish());
+        let mut input = InputManager::default();
+
+        assert!(replayer.step(&mut input));
+        assert!(input.just_pressed(VirtualKeyCode::A));
+        assert_eq!(input.key_presses, vec![VirtualKeyCode::A]);
+        assert_eq!(input.now(), ms(10));
+        input.update_inputs();
+
+        assert!(replayer.step(&mut input));
+        assert!(!input.just_pressed(VirtualKeyCode::A));
+        assert!(input.is_down(VirtualKeyCode::A));
+        assert_eq!(input.held_frames(VirtualKeyCode::A), 2);
+        assert_eq!(input.held_for(VirtualKeyCode::A), ms(15));
+        assert!(input.key_presses.is_empty());
+        input.update_inputs();
+
+        assert!(replayer.step(&mut input));
+        assert!(input.just_released(VirtualKeyCode::A));
+        assert!(!input.is_down(VirtualKeyCode::A));
+        // Pressed and let go within one frame still counts as pressed
+        assert!(input.just_pressed(VirtualKeyCode::B));
+        assert_eq!(input.key_presses, vec![VirtualKeyCode::B]);
+        input.update_inputs();
+
+        assert!(!replayer.step(&mut input));
+        assert!(replayer.is_finished());
+    }
+
+    #[test]
+    fn replayed_clicks_use_recorded_times() {
+        let click = |recorder: &mut InputRecorder, at: u64| {
+            recorder.record(InputEvent::MouseButton { button: MouseButton::Left, state: ElementState::Pressed }, ms(at));
+            recorder.next_frame(ms(at));
+            recorder.record(InputEvent::MouseButton { button: MouseButton::Left, state: ElementState::Released }, ms(at + 10));
+            recorder.next_frame(ms(at + 10));
+        };
+
+        let mut recorder = InputRecorder::new((800, 600), ms(0));
+        click(&mut recorder, 0);
+        click(&mut recorder, 100);
+        click(&mut recorder, 2000);
+
+        let mut replayer = InputReplayer::new(recorder.finish());
+        let mut input = InputManager::default();
+        let mut counts = Vec::new();
+
+        while replayer.step(&mut input) {
+            if input.just_pressed(MouseButton::Left) {
+                counts.push(input.click_count(MouseButton::Left));
+            }
+            input.update_inputs();
+        }
+
+        assert_eq!(counts, vec![1, 2, 1]);
+    }
+}
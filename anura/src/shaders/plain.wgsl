@@ -14,12 +14,17 @@ struct VertexOutput {
     @location(1) uv: vec2<f32>,
 };
 
+// Colours are given in srgb while the surface expects linear values
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    return select(pow((color + 0.055) / 1.055, vec3<f32>(2.4)), color / 12.92, color <= vec3<f32>(0.04045));
+}
+
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var out: VertexOutput;
 
     out.clip_position = camera * vec4<f32>(input.pos, 1.0);
-    out.color = input.color;
+    out.color = vec4<f32>(srgb_to_linear(input.color.rgb), input.color.a);
     out.uv = input.uv;
 
     return out;
@@ -0,0 +1,88 @@
+@group(0)
+@binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+struct VertexInput {
+    @location(0) pos: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) outline_color: vec4<f32>,
+    @location(4) glow_color: vec4<f32>,
+    @location(5) params: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) outline_color: vec4<f32>,
+    @location(3) glow_color: vec4<f32>,
+    @location(4) params: vec2<f32>,
+};
+
+// Colours are given in srgb while the surface expects linear values
+fn srgb_to_linear(color: vec4<f32>) -> vec4<f32> {
+    let rgb = select(pow((color.rgb + 0.055) / 1.055, vec3<f32>(2.4)), color.rgb / 12.92, color.rgb <= vec3<f32>(0.04045));
+    return vec4<f32>(rgb, color.a);
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.clip_position = camera * vec4<f32>(input.pos, 1.0);
+    out.color = srgb_to_linear(input.color);
+    out.uv = input.uv;
+    out.outline_color = srgb_to_linear(input.outline_color);
+    out.glow_color = srgb_to_linear(input.glow_color);
+    out.params = input.params;
+
+    return out;
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+
+@group(1)
+@binding(0)
+var s: sampler;
+@group(1)
+@binding(1)
+var t: texture_2d<f32>;
+
+// Non premultiplied "over" operator
+fn over(top: vec4<f32>, bottom: vec4<f32>) -> vec4<f32> {
+    let alpha = top.a + bottom.a * (1.0 - top.a);
+    if alpha <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+
+    let rgb = (top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / alpha;
+    return vec4<f32>(rgb, alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    var out: FragmentOutput;
+
+    // 0.5 is the glyph edge, bigger values are inside
+    let dist = textureSample(t, s, in.uv).a;
+
+    // Roughly one screen pixel worth of distance, keeps edges crisp at any size
+    let smoothing = max(fwidth(dist) * 0.75, 0.001);
+
+    let outline_edge = 0.5 - in.params.x;
+
+    let fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, dist);
+    let outline = smoothstep(outline_edge - smoothing, outline_edge + smoothing, dist) * step(0.0001, in.params.x);
+    let glow = smoothstep(outline_edge - in.params.y, outline_edge, dist) * step(0.0001, in.params.y);
+
+    var color = vec4<f32>(in.glow_color.rgb, in.glow_color.a * glow);
+    color = over(vec4<f32>(in.outline_color.rgb, in.outline_color.a * outline), color);
+    color = over(vec4<f32>(in.color.rgb, in.color.a * fill), color);
+
+    out.color = color;
+
+    return out;
+}
@@ -0,0 +1,32 @@
+// Glyphs from the atlas tinted with the vertex colour
+//
+// Pairs with the vertex shader of plain.wgsl, which already decoded the colour to linear space.
+// The surface is srgb so the coverage gets blended in linear space by the gpu itself.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+};
+
+@group(1)
+@binding(0)
+var s: sampler;
+@group(1)
+@binding(1)
+var t: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    var out: FragmentOutput;
+
+    let coverage = textureSampleLevel(t, s, in.uv, 0.0).a;
+
+    out.color = vec4<f32>(in.color.rgb, in.color.a * coverage);
+
+    return out;
+}
@@ -0,0 +1,238 @@
+use std::{
+    any::Any,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use ahash::AHashMap;
+use winit::event_loop::EventLoopProxy;
+
+use crate::{event::AnuraUserEvent, handle::{NodeType, UntypedHandle}};
+
+/// Whatever the task returned, downcast it to get the actual value back
+pub type TaskOutput = Box<dyn Any + Send>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TaskId(usize);
+
+/// Shared flag telling a background task it should stop
+///
+/// Long running closures should check it every now and then, futures are
+/// simply not polled anymore once its set
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The view (by its id) and the node inside of it which spawned a task
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct TaskOwner {
+    pub view: usize,
+    pub node: NodeType,
+    /// Generation of the widget's slot, handles get reused once a widget is removed
+    pub generation: u32,
+}
+
+pub struct FinishedTask {
+    pub(crate) owner: TaskOwner,
+    pub(crate) id: TaskId,
+    pub(crate) output: TaskOutput,
+}
+
+/// Runs closures and futures on background threads and collects their results
+/// so they can be handed back to their owners on the main thread
+pub struct TaskManager {
+    sender: Sender<(TaskId, TaskOutput)>,
+    receiver: Receiver<(TaskId, TaskOutput)>,
+    running: AHashMap<TaskId, (TaskOwner, CancellationToken)>,
+    finished: Vec<FinishedTask>,
+    proxy: Option<EventLoopProxy<AnuraUserEvent>>,
+    next_id: usize,
+}
+
+impl Default for TaskManager {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            sender,
+            receiver,
+            running: AHashMap::new(),
+            finished: Vec::new(),
+            proxy: None,
+            next_id: 0,
+        }
+    }
+}
+
+impl TaskManager {
+    /// Lets finished tasks wake up the event loop, without it results are
+    /// only picked up on the next update
+    pub fn set_event_loop_proxy(&mut self, proxy: EventLoopProxy<AnuraUserEvent>) {
+        self.proxy = Some(proxy);
+    }
+
+    pub(crate) fn spawn_thread<T, F>(&mut self, owner: TaskOwner, f: F) -> TaskId
+    where
+        T: Send + 'static,
+        F: FnOnce(CancellationToken) -> T + Send + 'static,
+    {
+        let (id, token) = self.register(owner);
+        let sender = self.sender.clone();
+        let proxy = self.proxy.clone();
+
+        thread::spawn(move || {
+            let output = f(token.clone());
+
+            if !token.is_cancelled() {
+                send_result(&sender, &proxy, id, Box::new(output));
+            }
+        });
+
+        id
+    }
+
+    pub(crate) fn spawn_future<T, F>(&mut self, owner: TaskOwner, future: F) -> TaskId
+    where
+        T: Send + 'static,
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (id, token) = self.register(owner);
+        let sender = self.sender.clone();
+        let proxy = self.proxy.clone();
+
+        thread::spawn(move || {
+            if let Some(output) = block_on(future, &token) {
+                send_result(&sender, &proxy, id, Box::new(output));
+            }
+        });
+
+        id
+    }
+
+    fn register(&mut self, owner: TaskOwner) -> (TaskId, CancellationToken) {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+
+        let token = CancellationToken::default();
+        self.running.insert(id, (owner, token.clone()));
+
+        (id, token)
+    }
+
+    pub fn cancel(&mut self, id: TaskId) {
+        if let Some((_, token)) = self.running.remove(&id) {
+            token.cancel();
+        }
+        self.finished.retain(|task| task.id != id);
+    }
+
+    /// Cancels every task spawned by the given view and drops their undelivered results
+    pub(crate) fn cancel_view(&mut self, view: usize) {
+        self.running.retain(|_, (owner, token)| {
+            if owner.view == view {
+                token.cancel();
+                return false;
+            }
+            true
+        });
+        self.finished.retain(|task| task.owner.view != view);
+    }
+
+    /// Cancels every task spawned by a removed widget and drops their undelivered results
+    pub(crate) fn cancel_widget(&mut self, view: usize, handle: UntypedHandle, generation: u32) {
+        let spawned_by = |owner: &TaskOwner| {
+            owner.view == view && matches!(owner.node, NodeType::Handle(node) if node == handle) && owner.generation == generation
+        };
+
+        self.running.retain(|_, (owner, token)| {
+            if spawned_by(owner) {
+                token.cancel();
+                return false;
+            }
+            true
+        });
+        self.finished.retain(|task| !spawned_by(&task.owner));
+    }
+
+    pub fn is_running(&self, id: TaskId) -> bool {
+        self.running.contains_key(&id)
+    }
+
+    /// Moves results which arrived from the background threads into the finished queue
+    pub(crate) fn poll(&mut self) {
+        while let Ok((id, output)) = self.receiver.try_recv() {
+            //Cancelled tasks are no longer in the running map
+            if let Some((owner, _)) = self.running.remove(&id) {
+                self.finished.push(FinishedTask { owner, id, output });
+            }
+        }
+    }
+
+    /// Takes out the results belonging to the given view
+    pub(crate) fn take_finished(&mut self, view: usize) -> Vec<FinishedTask> {
+        let (taken, rest) = std::mem::take(&mut self.finished)
+            .into_iter()
+            .partition(|task| task.owner.view == view);
+
+        self.finished = rest;
+        taken
+    }
+}
+
+fn send_result(
+    sender: &Sender<(TaskId, TaskOutput)>,
+    proxy: &Option<EventLoopProxy<AnuraUserEvent>>,
+    id: TaskId,
+    output: TaskOutput,
+) {
+    // Receiver is gone if the app already closed, nothing to do then
+    if sender.send((id, output)).is_ok() {
+        if let Some(proxy) = proxy {
+            _ = proxy.send_event(AnuraUserEvent::TaskFinished);
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Minimal executor which drives a single future on the current thread
+fn block_on<F: Future>(future: F, token: &CancellationToken) -> Option<F::Output> {
+    let mut future = std::pin::pin!(future);
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        if token.is_cancelled() {
+            return None;
+        }
+
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return Some(output),
+            //Timeout so cancellation is noticed even if nobody wakes us
+            Poll::Pending => thread::park_timeout(Duration::from_millis(50)),
+        }
+    }
+}
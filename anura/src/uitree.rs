@@ -1,6 +1,9 @@
 use std::marker::PhantomData;
 
 use crate::{
+    animation::AnimationManager,
+    drag::DragManager,
+    gesture::GestureTargets,
     app::App,
     arena::Arena,
     handle::{NodeType, TypedHandle, UntypedHandle},
@@ -13,7 +16,21 @@ pub struct UiTree<A, V, R> {
     pub widget_arena: Arena<Box<dyn Widget<A, V, R>>>,
     pub children_arena: Arena<Vec<UntypedHandle>>,
     pub parent_arena: Arena<NodeType>,
+    pub animations: AnimationManager<A, V, R>,
     pub(crate) pending_init: Vec<UntypedHandle>,
+    pub(crate) disabled: Vec<UntypedHandle>,
+    /// Widgets which got disabled or enabled and have to be rerendered
+    pub(crate) toggled: Vec<UntypedHandle>,
+    /// Widget receiving keyboard input
+    pub(crate) focused: Option<UntypedHandle>,
+    pub(crate) drag: DragManager,
+    pub(crate) gestures: GestureTargets,
+    /// Sent to the view as `ViewEvent::Action` once every widget is updated
+    pub(crate) pending_actions: Vec<String>,
+    /// Bumped every time the widget in a slot is removed, by handle index
+    pub(crate) generations: Vec<u32>,
+    /// Removed widgets whose tasks still have to be cancelled, with their generation
+    pub(crate) removed: Vec<(UntypedHandle, u32)>,
 }
 
 impl<A, V, R> Default for UiTree<A, V, R>
@@ -27,7 +44,16 @@ where
             children_arena: Arena::new(),
             parent_arena: Arena::new(),
             widget_arena: Arena::new(),
+            animations: AnimationManager::default(),
             pending_init: Vec::new(),
+            disabled: Vec::new(),
+            toggled: Vec::new(),
+            focused: None,
+            drag: DragManager::default(),
+            gestures: GestureTargets::default(),
+            pending_actions: Vec::new(),
+            generations: Vec::new(),
+            removed: Vec::new(),
         }
     }
 }
@@ -99,7 +125,24 @@ where
     {
         let handle = handle.into();
 
+        if self.focused == Some(handle) {
+            self.focused = None;
+        }
+        self.drag.remove(handle);
+        self.gestures.remove(handle);
+        // The next widget in this slot starts out fresh
+        self.disabled.retain(|h| *h != handle);
+        self.toggled.retain(|h| *h != handle);
+        self.pending_init.retain(|h| *h != handle);
+        self.animations.stop_all(&handle);
+
         if self.widget_arena.remove(handle.index).is_some() {
+            if self.generations.len() <= handle.index {
+                self.generations.resize(handle.index + 1, 0);
+            }
+            self.removed.push((handle, self.generations[handle.index]));
+            self.generations[handle.index] += 1;
+
             let removed_parent_enum = self.parent_arena.remove(handle.index).unwrap();
             //This node's children's parent is changed to the removed node parent
             // Parent > Removed node > Children
@@ -124,6 +167,57 @@ where
         }
     }
 
+    /// How often the widget in the handle's slot has been replaced
+    pub(crate) fn generation(&self, handle: &impl AsUntypedHandle) -> u32 {
+        self.generations.get(handle.handle().index).copied().unwrap_or(0)
+    }
+
+    /// Disabled widgets dont receive any events and lose focus but are still rendered,
+    /// see `RenderContext::is_disabled`
+    pub fn disable(&mut self, handle: &impl AsUntypedHandle) {
+        let handle = handle.handle();
+        if !self.disabled.contains(&handle) {
+            self.disabled.push(handle);
+            self.toggled.push(handle);
+        }
+
+        if self.focused == Some(handle) {
+            self.focused = None;
+        }
+    }
+
+    pub fn enable(&mut self, handle: &impl AsUntypedHandle) {
+        let handle = handle.handle();
+        if self.disabled.contains(&handle) {
+            self.disabled.retain(|h| *h != handle);
+            self.toggled.push(handle);
+        }
+    }
+
+    #[must_use]
+    pub fn is_disabled(&self, handle: &impl AsUntypedHandle) -> bool {
+        self.disabled.contains(&handle.handle())
+    }
+
+    pub fn focus(&mut self, handle: &impl AsUntypedHandle) {
+        self.focused = Some(handle.handle());
+    }
+
+    /// Takes keyboard focus away from whichever widget has it
+    pub fn blur(&mut self) {
+        self.focused = None;
+    }
+
+    #[must_use]
+    pub fn focused(&self) -> Option<UntypedHandle> {
+        self.focused
+    }
+
+    #[must_use]
+    pub fn is_focused(&self, handle: &impl AsUntypedHandle) -> bool {
+        self.focused == Some(handle.handle())
+    }
+
     #[must_use]
     pub fn get_children_handles(&self, handle: &impl AsUntypedHandle) -> Vec<UntypedHandle> {
         let handle = handle.handle();
@@ -1,12 +1,17 @@
 use std::error::Error;
 
+use winit::event::{MouseButton, VirtualKeyCode};
+
 use crate::{
     app::{App, AnuraApp},
+    drag::{ActiveDrag, DragInfo, DragPreview, FileDrop, PREVIEW_Z},
+    gesture::GesturePhase,
     context::{AnuraContext, RenderContext},
     event::{ViewEvent, WidgetEvent},
     graphics::AnuraRenderer,
-    prelude::{AnuraError, Layout, NodeType},
+    prelude::{AnuraError, ErrorOrigin, Layout, NodeType},
     uitree::UiTree, handle::UntypedHandle,
+    task::FinishedTask,
 };
 
 pub trait View<A, R>
@@ -25,6 +30,17 @@ where
 pub trait ViewWrapperTrait<A: App<R>, R: AnuraRenderer> {
     /// Updates the underlying View<A>
     fn update(&mut self, app: &mut AnuraApp<'_, A, R>) -> Result<(), AnuraError<R::ErrorMessage>>;
+    /// Stops sending events to the widget
+    fn disable_widget(&mut self, handle: UntypedHandle);
+    /// Hands the result of a finished task to whoever spawned it
+    fn deliver_task(&mut self, app: &mut AnuraApp<'_, A, R>, task: FinishedTask) -> Result<(), AnuraError<R::ErrorMessage>>;
+    /// Sends an event to the focused widget, it's dropped if nothing is focused
+    fn dispatch_focused(&mut self, app: &mut AnuraApp<'_, A, R>, event: WidgetEvent);
+    fn has_focus(&self) -> bool;
+    /// Hands files dragged onto the window to the drag and drop of the view
+    fn receive_files(&mut self, events: Vec<FileDrop>);
+    /// Sends an event to the underlying View<A>
+    fn dispatch_view_event(&mut self, app: &mut AnuraApp<'_, A, R>, event: ViewEvent) -> Result<(), AnuraError<R::ErrorMessage>>;
     fn render_view(
         &mut self,
         _window_size: (u32, u32),
@@ -39,9 +55,9 @@ pub struct ViewWrapper<A, V, R> {
 
 impl<'a, A, V, R> ViewWrapper<A, V, R>
 where
-    A: App<R>,
+    A: App<R> + 'static,
     V: View<A, R>,
-    R: AnuraRenderer,
+    R: AnuraRenderer + 'static,
 {
     pub fn new(mut view: V, app: &mut AnuraApp<'_, A, R>) -> ViewWrapper<A, V, R> {
         let mut ui_tree = UiTree::<A, V, R>::default();
@@ -52,12 +68,205 @@ where
             current: NodeType::Root,
         };
 
-        view.handle_event(&mut context, ViewEvent::Init);
+        if let Err(err) = view.handle_event(&mut context, ViewEvent::Init) {
+            let origin = ErrorOrigin::View(app.get_current_view_id());
+            app.report_error(origin, err);
+        }
 
         Self { ui_tree, view }
     }
 }
 
+impl<A, V, R> ViewWrapper<A, V, R>
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+{
+    /// Sends an event to a single widget
+    pub(crate) fn dispatch_widget_event(
+        &mut self,
+        app: &mut AnuraApp<'_, A, R>,
+        handle: &UntypedHandle,
+        event: WidgetEvent,
+    ) -> Result<(), AnuraError<R::ErrorMessage>> {
+        //Widget might have been removed in the meantime
+        if self.ui_tree.get_untyped_ref(handle).is_none() || self.ui_tree.is_disabled(handle) {
+            return Ok(());
+        }
+
+        self.ui_tree.run_on_moved_out(handle, |ui_tree, widget| {
+            let mut context = AnuraContext {
+                app,
+                ui_tree,
+                current: NodeType::Handle(*handle),
+            };
+
+            widget.handle_event(&mut context, &mut self.view, event)
+        })
+    }
+
+    /// Advances all running animations, rerenders the animated widgets and
+    /// notifies widgets whose animations have finished
+    fn tick_animations(&mut self, app: &mut AnuraApp<'_, A, R>) {
+        // Move the manager out so it can mutate the tree it lives in
+        let mut animations = std::mem::take(&mut self.ui_tree.animations);
+        let tick = animations.tick(&mut self.ui_tree, app.input_manager.now());
+        self.ui_tree.animations = animations;
+
+        for handle in tick.animated {
+            let mut context = AnuraContext {
+                app,
+                ui_tree: &mut self.ui_tree,
+                current: NodeType::Handle(handle),
+            };
+
+            context.issue_rerender();
+        }
+
+        for (handle, id) in tick.finished {
+            if let Err(err) = self.dispatch_widget_event(app, &handle, WidgetEvent::AnimationFinished(id)) {
+                report_widget_error(app, handle, err);
+            }
+        }
+    }
+}
+
+impl<A, V, R> ViewWrapper<A, V, R>
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+{
+    /// Sends the gestures of this frame to the widgets they started on
+    fn dispatch_gestures(&mut self, app: &mut AnuraApp<'_, A, R>) {
+        let gestures = app.input_manager.gestures.clone();
+
+        for gesture in gestures {
+            let targets = &mut self.ui_tree.gestures;
+
+            let target = match gesture.phase() {
+                Some(GesturePhase::Moved | GesturePhase::Ended) => targets.captured,
+                _ => targets.target_at(gesture.origin(), |handle| app.widget_layout(&handle)),
+            };
+
+            match gesture.phase() {
+                Some(GesturePhase::Started) => targets.captured = target,
+                Some(GesturePhase::Ended) => targets.captured = None,
+                _ => {}
+            }
+
+            if let Some(target) = target {
+                if let Err(err) = self.dispatch_widget_event(app, &target, WidgetEvent::Gesture(gesture)) {
+                    report_widget_error(app, target, err);
+                }
+            }
+        }
+    }
+
+    /// Follows the drag in progress with the mouse and tells drop targets about it
+    fn update_drag(&mut self, app: &mut AnuraApp<'_, A, R>) {
+        let drag = &mut self.ui_tree.drag;
+        let cancel_requested = std::mem::take(&mut drag.cancel_requested);
+        let files_dropped = std::mem::take(&mut drag.files_dropped);
+
+        let (source, payload, hovered) = match &drag.active {
+            Some(active) => (active.source, active.payload.clone(), active.hovered),
+            None => return,
+        };
+
+        let input = &app.input_manager;
+        let position = (input.mouse_position.x as f32, input.mouse_position.y as f32);
+        let released = match source {
+            Some(_) => !input.is_down(MouseButton::Left),
+            None => files_dropped,
+        };
+        let cancelled = cancel_requested || input.just_pressed(VirtualKeyCode::Escape);
+
+        let target = match cancelled {
+            true => None,
+            false => drag.target_at(position, &payload, source, |handle| app.widget_layout(&handle)),
+        };
+
+        let info = DragInfo { payload, source, position };
+        let mut events = Vec::new();
+
+        if target != hovered {
+            if let Some(hovered) = hovered {
+                events.push((hovered, WidgetEvent::DragLeave(info.clone())));
+            }
+            if let Some(target) = target {
+                events.push((target, WidgetEvent::DragEnter(info.clone())));
+            }
+        }
+
+        if released || cancelled {
+            drag.active = None;
+
+            if let Some(target) = target {
+                events.push((target, WidgetEvent::Drop(info)));
+            }
+            if let Some(source) = source {
+                events.push((source, WidgetEvent::DragEnd { dropped: target.is_some() }));
+            }
+        } else {
+            if let Some(active) = &mut drag.active {
+                active.hovered = target;
+            }
+            if let Some(target) = target {
+                events.push((target, WidgetEvent::DragOver(info)));
+            }
+        }
+
+        for (handle, event) in events {
+            if let Err(err) = self.dispatch_widget_event(app, &handle, event) {
+                report_widget_error(app, handle, err);
+            }
+        }
+    }
+
+    /// Draws the dragged widget again where the mouse is
+    fn render_drag_preview(&mut self, app: &mut AnuraApp<'_, A, R>) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>> {
+        let (source, grab_offset) = match &self.ui_tree.drag.active {
+            Some(ActiveDrag { source: Some(source), preview: DragPreview::Widget, grab_offset, .. }) => (*source, *grab_offset),
+            _ => return Ok(Vec::new()),
+        };
+
+        let layout = match app.widget_layout(&source) {
+            Some(layout) => layout,
+            None => return Ok(Vec::new()),
+        };
+
+        let mouse = app.input_manager.mouse_position;
+        let preview_layout = Layout {
+            origin: (mouse.x as f32 - grab_offset.0, mouse.y as f32 - grab_offset.1, PREVIEW_Z),
+            available_space: layout.available_space,
+        };
+
+        let mut context = RenderContext {
+            app,
+            ui_tree: &self.ui_tree,
+            current: NodeType::Root,
+            preview: true,
+        };
+
+        context.render(&source, &mut self.view, preview_layout)
+    }
+}
+
+fn report_widget_error<A, R>(app: &mut AnuraApp<'_, A, R>, handle: UntypedHandle, err: AnuraError<R::ErrorMessage>)
+where
+    A: App<R> + 'static,
+    R: AnuraRenderer + 'static,
+{
+    let origin = ErrorOrigin::Widget {
+        view: app.get_current_view_id(),
+        handle,
+    };
+
+    app.report_error(origin, err);
+}
+
 impl<A, V, R> ViewWrapperTrait<A, R> for ViewWrapper<A, V, R>
 where
     A: App<R> + 'static,
@@ -70,46 +279,140 @@ where
         // maybe theres a better solution than .clone()?
         let mut pending_init = self.ui_tree.pending_init.clone();
 
+        self.tick_animations(app);
+
         let mut context = AnuraContext { 
             app, 
             ui_tree: &mut self.ui_tree,
             current: NodeType::Root,
         };
 
-        self.view.handle_event(&mut context, ViewEvent::Update)?;
+        if let Err(err) = self.view.handle_event(&mut context, ViewEvent::Update) {
+            let origin = ErrorOrigin::View(app.get_current_view_id());
+            app.report_error(origin, err);
+        }
 
         let handles = self.ui_tree.get_all_handles().collect::<Vec<_>>();
 
         for handle in &handles {
+            if self.ui_tree.is_disabled(handle) { continue }
+
             //move the widget out to avoid aliasing refs
-            let _: Result<(), AnuraError<R::ErrorMessage>> = self.ui_tree.run_on_moved_out(
+            let result = self.ui_tree.run_on_moved_out(
                 &handle, 
                 |mut ui_tree, widget| {
                     let mut context = AnuraContext { 
                         app, 
                         ui_tree: &mut ui_tree,
-                        current: NodeType::Handle(handle.clone())
+                        current: NodeType::Handle(*handle)
                     };
 
                     if pending_init.contains(&handle) {
-                        if let Err(err) = widget.handle_event(&mut context, &mut self.view, WidgetEvent::Init) {
-                            return Err(err);
-                        }
+                        //A failed init is retried next frame, the widget isnt updated or rendered
+                        //until then. Disabling it from the error hook stops the retries
+                        widget.handle_event(&mut context, &mut self.view, WidgetEvent::Init)?;
 
                         pending_init.retain(|h| *h != *handle);
+                        context.issue_rerender();
                     }
 
                     widget.handle_event(&mut context, &mut self.view, WidgetEvent::Update)
                 }
             );
 
+            if let Err(err) = result {
+                report_widget_error(app, *handle, err);
+            }
         }
 
         self.ui_tree.pending_init = pending_init;
 
+        for action in std::mem::take(&mut self.ui_tree.pending_actions) {
+            if let Err(err) = self.dispatch_view_event(app, ViewEvent::Action(action)) {
+                let origin = ErrorOrigin::View(app.get_current_view_id());
+                app.report_error(origin, err);
+            }
+        }
+
+        self.dispatch_gestures(app);
+        self.update_drag(app);
+
+        // Widgets draw themselves differently while disabled
+        for handle in std::mem::take(&mut self.ui_tree.toggled) {
+            let mut context = AnuraContext {
+                app,
+                ui_tree: &mut self.ui_tree,
+                current: NodeType::Handle(handle),
+            };
+            context.issue_rerender();
+        }
+
+        let view_id = app.get_current_view_id();
+        for (handle, generation) in std::mem::take(&mut self.ui_tree.removed) {
+            app.task_manager.cancel_widget(view_id, handle, generation);
+        }
+
         Ok(())
     }
 
+    fn disable_widget(&mut self, handle: UntypedHandle) {
+        self.ui_tree.disable(&handle);
+    }
+
+    fn deliver_task(&mut self, app: &mut AnuraApp<'_, A, R>, task: FinishedTask) -> Result<(), AnuraError<R::ErrorMessage>> {
+        let FinishedTask { owner, id, output } = task;
+
+        match owner.node {
+            NodeType::Root => {
+                let mut context = AnuraContext {
+                    app,
+                    ui_tree: &mut self.ui_tree,
+                    current: NodeType::Root,
+                };
+
+                self.view.handle_event(&mut context, ViewEvent::TaskFinished { id, output })
+            }
+            // The widget which spawned it is gone, another one might have taken its slot since
+            NodeType::Handle(handle) if owner.generation != self.ui_tree.generation(&handle) => Ok(()),
+            NodeType::Handle(handle) => {
+                if let Err(err) = self.dispatch_widget_event(app, &handle, WidgetEvent::TaskFinished { id, output }) {
+                    report_widget_error(app, handle, err);
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn dispatch_focused(&mut self, app: &mut AnuraApp<'_, A, R>, event: WidgetEvent) {
+        let handle = match self.ui_tree.focused() {
+            Some(handle) => handle,
+            None => return,
+        };
+
+        if let Err(err) = self.dispatch_widget_event(app, &handle, event) {
+            report_widget_error(app, handle, err);
+        }
+    }
+
+    fn has_focus(&self) -> bool {
+        self.ui_tree.focused().is_some()
+    }
+
+    fn receive_files(&mut self, events: Vec<FileDrop>) {
+        self.ui_tree.drag.receive_files(events);
+    }
+
+    fn dispatch_view_event(&mut self, app: &mut AnuraApp<'_, A, R>, event: ViewEvent) -> Result<(), AnuraError<R::ErrorMessage>> {
+        let mut context = AnuraContext {
+            app,
+            ui_tree: &mut self.ui_tree,
+            current: NodeType::Root,
+        };
+
+        self.view.handle_event(&mut context, event)
+    }
+
     fn render_view(
         &mut self,
         window_size: (u32, u32),
@@ -133,7 +436,8 @@ where
                     let mut context = RenderContext {
                         app,
                         ui_tree: &self.ui_tree,
-                        current: NodeType::Root
+                        current: NodeType::Root,
+                        preview: false,
                     };
         
                     command_vec.append(&mut context.render(&handle, &mut self.view, Layout {
@@ -147,6 +451,9 @@ where
             }
         }
 
+        // Not cached, it moves every frame
+        command_vec.append(&mut self.render_drag_preview(app)?);
+
 
         Ok(command_vec)
     }
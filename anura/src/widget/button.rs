@@ -0,0 +1,298 @@
+use std::path::PathBuf;
+
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::{
+    app::App,
+    context::{AnuraContext, RenderContext},
+    error::AnuraError,
+    event::WidgetEvent,
+    font::{Font, TextLayout, TextLayoutOptions, TextWrap, VerticalAlign},
+    graphics::{AnuraRenderCommand, AnuraRenderer},
+    prelude::{DefaultMesh, DefaultVertex, FromAnuraMesh, FromAnuraUniform},
+    view::View,
+    widget::Layout,
+};
+
+use super::{atlas_commands, Widget};
+
+/// What a button currently looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonVisual {
+    Normal,
+    Hovered,
+    Pressed,
+    Focused,
+    Disabled,
+}
+
+/// Mouse and keyboard state of a button, see `Button::visual`
+#[derive(Debug, Clone, Default)]
+pub struct ButtonState {
+    hovered: bool,
+    /// The mouse went down on the button and hasn't been let go yet
+    pressed: bool,
+    /// Enter or space is held while the button is focused
+    key_pressed: bool,
+    focused: bool,
+}
+
+/// Clickable background with a text and/or icon centered on it
+///
+/// Clicking it, or pressing enter or space while it's focused, sends `action`
+/// to the view as `ViewEvent::Action`. Disable it with `UiTree::disable` to grey it out
+pub struct Button {
+    pub text: String,
+    /// Drawn left of the text
+    pub icon: Option<PathBuf>,
+    pub icon_size: (f32, f32),
+    /// Space between the icon and the text
+    pub spacing: f32,
+    pub font: Font,
+    pub text_color: (f32, f32, f32, f32),
+    pub background: (f32, f32, f32, f32),
+    pub hover_background: (f32, f32, f32, f32),
+    pub pressed_background: (f32, f32, f32, f32),
+    pub disabled_background: (f32, f32, f32, f32),
+    pub disabled_text_color: (f32, f32, f32, f32),
+    /// Outline drawn while the button is focused
+    pub focus_color: (f32, f32, f32, f32),
+    pub action: Option<String>,
+    pub state: ButtonState,
+}
+
+impl Default for Button {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            icon: None,
+            icon_size: (16.0, 16.0),
+            spacing: 6.0,
+            font: Font::Default,
+            text_color: (0.0, 0.0, 0.0, 1.0),
+            background: (0.85, 0.85, 0.85, 1.0),
+            hover_background: (0.9, 0.9, 0.9, 1.0),
+            pressed_background: (0.7, 0.7, 0.7, 1.0),
+            disabled_background: (0.8, 0.8, 0.8, 1.0),
+            disabled_text_color: (0.55, 0.55, 0.55, 1.0),
+            focus_color: (0.3, 0.5, 0.9, 1.0),
+            action: None,
+            state: ButtonState::default(),
+        }
+    }
+}
+
+impl Button {
+    pub fn new(text: impl Into<String>, action: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            action: Some(action.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Pressed wins over hovered which wins over focused, `disabled` is `UiTree::is_disabled`
+    pub fn visual(&self, disabled: bool) -> ButtonVisual {
+        let state = &self.state;
+
+        if disabled {
+            ButtonVisual::Disabled
+        } else if state.key_pressed || (state.pressed && state.hovered) {
+            ButtonVisual::Pressed
+        } else if state.hovered {
+            ButtonVisual::Hovered
+        } else if state.focused {
+            ButtonVisual::Focused
+        } else {
+            ButtonVisual::Normal
+        }
+    }
+
+    fn layout_options(&self, height: f32) -> TextLayoutOptions {
+        TextLayoutOptions {
+            max_height: Some(height),
+            vertical_align: VerticalAlign::Center,
+            wrap: TextWrap::None,
+            ..Default::default()
+        }
+    }
+}
+
+impl<A, V, R> Widget<A, V, R> for Button
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+    R::Mesh: FromAnuraMesh,
+    R::Uniform: FromAnuraUniform,
+    R::RenderCommand: AnuraRenderCommand<Renderer = R>
+{
+    fn handle_event(
+        &mut self,
+        ctx: &mut AnuraContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        event: WidgetEvent,
+    ) -> Result<(), AnuraError<R::ErrorMessage>> {
+        match event {
+            WidgetEvent::Init => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+            }
+            WidgetEvent::Update => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+
+                // Disabled widgets don't get updated, so this one isn't
+                let before = (self.visual(false), self.state.focused);
+
+                // Nothing can be clicked before the first render
+                let layout = match ctx.layout() {
+                    Some(layout) => layout,
+                    None => return Ok(()),
+                };
+
+                let (x, y, _) = layout.origin;
+                let (width, height) = layout.available_space;
+
+                let input = &ctx.app.input_manager;
+                let mouse = (input.mouse_position.x as f32, input.mouse_position.y as f32);
+                let hovered = mouse.0 >= x && mouse.0 <= x + width && mouse.1 >= y && mouse.1 <= y + height;
+
+                let just_pressed = input.just_pressed(MouseButton::Left);
+                let released = input.just_released(MouseButton::Left);
+
+                let mut clicked = false;
+
+                if just_pressed {
+                    self.state.pressed = hovered;
+
+                    if hovered {
+                        ctx.focus();
+                    } else if ctx.is_focused() {
+                        ctx.ui_tree.blur();
+                    }
+                }
+
+                // Only a click which is let go on the button counts, dragging off of it backs out
+                if released && self.state.pressed {
+                    self.state.pressed = false;
+                    clicked |= hovered;
+                }
+
+                self.state.hovered = hovered;
+                self.state.focused = ctx.is_focused();
+
+                if self.state.focused {
+                    // Keys bound to actions are taken out of `key_presses` and don't click
+                    let input = &ctx.app.input_manager;
+                    let keys = [VirtualKeyCode::Return, VirtualKeyCode::NumpadEnter, VirtualKeyCode::Space];
+                    let key_clicked = keys.iter().any(|key| input.key_presses.contains(key));
+
+                    clicked |= key_clicked;
+                    self.state.key_pressed = key_clicked || (self.state.key_pressed && keys.iter().any(|key| input.is_down(*key)));
+                } else {
+                    self.state.key_pressed = false;
+                }
+
+                if let (true, Some(action)) = (clicked, &self.action) {
+                    ctx.send_action(action.clone());
+                }
+
+                if (self.visual(false), self.state.focused) != before {
+                    ctx.issue_rerender();
+                }
+            }
+            WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::ImePreedit { .. }
+            | WidgetEvent::ImeCommit(_)
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
+        };
+
+        Ok(())
+    }
+
+    fn generate_mesh(
+        &self,
+        ctx: &mut RenderContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        layout: Layout,
+    ) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>> {
+        let plain_shader = crate::graphics::PLAIN_SHADER;
+        let text_shader = crate::graphics::TEXT_SHADER;
+        let image_shader = crate::graphics::IMAGE_SHADER;
+
+        let (x, y, z) = layout.origin;
+        let (width, height) = layout.available_space;
+
+        let visual = self.visual(ctx.is_disabled());
+        let (background, text_color) = match visual {
+            ButtonVisual::Disabled => (self.disabled_background, self.disabled_text_color),
+            ButtonVisual::Pressed => (self.pressed_background, self.text_color),
+            ButtonVisual::Hovered => (self.hover_background, self.text_color),
+            ButtonVisual::Normal | ButtonVisual::Focused => (self.background, self.text_color),
+        };
+
+        let font = ctx.app.font_manager.get_font_mut(&self.font)?;
+        let thickness = (font.size / 8.0).max(2.0);
+
+        let text_layout = TextLayout::new(font, &self.text, &self.layout_options(height));
+
+        // Icon and text are centered together
+        let icon_width = match self.icon {
+            Some(_) => self.icon_size.0 + if self.text.is_empty() { 0.0 } else { self.spacing },
+            None => 0.0,
+        };
+        let content_x = x + ((width - icon_width - text_layout.size.0) / 2.0).max(0.0);
+
+        let mut quads = DefaultMesh::<DefaultVertex>::quad((x, y, z), (width, height), (0.0, 0.0, 0.0, 0.0), background);
+
+        // The outline stays while hovering or pressing, otherwise focus would flicker in and out
+        if self.state.focused && visual != ButtonVisual::Disabled {
+            for (origin, size) in [
+                ((x, y), (width, thickness)),
+                ((x, y + height - thickness), (width, thickness)),
+                ((x, y), (thickness, height)),
+                ((x + width - thickness, y), (thickness, height)),
+            ] {
+                quads.merge(&mut DefaultMesh::quad((origin.0, origin.1, z + 0.5), size, (0.0, 0.0, 0.0, 0.0), self.focus_color));
+            }
+        }
+        quads.possibly_trasparent();
+
+        let meshes = DefaultMesh::<DefaultVertex>::text_layout((content_x + icon_width, y, z + 1.0), &text_layout, font, text_color);
+
+        let mut commands = Vec::new();
+
+        let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", plain_shader, "fs_main")?;
+        let mut command = R::RenderCommand::new(R::Mesh::convert(&quads), shader);
+        command.add_uniform(ctx.app.renderer.camera_uniform());
+        commands.push(command);
+
+        commands.extend(atlas_commands(&mut ctx.app.renderer, font, meshes, plain_shader, text_shader)?);
+
+        if let Some(icon) = &self.icon {
+            let mut mesh = DefaultMesh::<DefaultVertex>::quad(
+                (content_x, y + (height - self.icon_size.1) / 2.0, z + 1.0),
+                self.icon_size,
+                (0.0, 0.0, 1.0, 1.0),
+                (0.0, 0.0, 0.0, 1.0),
+            );
+            mesh.possibly_trasparent();
+
+            let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", image_shader, "fs_main")?;
+            let texture = ctx.app.renderer.load_texture(icon)?;
+
+            let mut command = R::RenderCommand::new(R::Mesh::convert(&mesh), shader);
+            command.add_uniform(ctx.app.renderer.camera_uniform());
+            command.add_texture(texture);
+            commands.push(command);
+        }
+
+        Ok(commands)
+    }
+}
@@ -42,6 +42,17 @@ where
         match event {
             WidgetEvent::Init => {},
             WidgetEvent::Update => {}
+            WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::ImePreedit { .. }
+            | WidgetEvent::ImeCommit(_)
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
         };
 
         Ok(())
@@ -55,7 +66,7 @@ where
     ) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>> {
         let plain_shader = crate::graphics::PLAIN_SHADER;
         let image_shader = crate::graphics::IMAGE_SHADER;
-        let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", image_shader, "fs_main");
+        let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", image_shader, "fs_main")?;
 
         let mut mesh = DefaultMesh::<DefaultVertex>::quad(
             layout.origin, 
@@ -67,7 +78,7 @@ where
         
         let camera_uniform = ctx.app.renderer.camera_uniform();
 
-        let texture = ctx.app.renderer.load_texture(&self.image_path);
+        let texture = ctx.app.renderer.load_texture(&self.image_path)?;
 
         let mut command = R::RenderCommand::new(R::Mesh::convert(&mesh), shader);
         command.add_uniform(camera_uniform);
@@ -3,26 +3,66 @@ pub mod text;
 
 pub use text::*;
 
+pub mod text_edit;
+pub use text_edit::*;
+
+pub mod text_input;
+pub use text_input::*;
+
+pub mod text_area;
+pub use text_area::*;
+
+pub mod rich_text;
+pub use rich_text::*;
+
 pub mod vertical_con;
 pub use vertical_con::*;
 
 pub mod image;
 pub use crate::widget::image::*;
 
+pub mod button;
+pub use button::*;
+
 use crate::{
     app::App,
     context::{AnuraContext, RenderContext},
     error::AnuraError,
     event::{WidgetEvent},
-    graphics::AnuraRenderer, view::View,
+    graphics::AnuraRenderer, view::View, handle::UntypedHandle,
 
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Layout {
     pub origin: (f32, f32, f32),
     pub available_space: (f32, f32),
 }
 
+/// Frontmost and innermost of `targets` under `position`, used to pick drop and gesture targets
+pub(crate) fn widget_at(
+    targets: impl IntoIterator<Item = UntypedHandle>,
+    position: (f32, f32),
+    layout_of: impl Fn(UntypedHandle) -> Option<Layout>,
+) -> Option<UntypedHandle> {
+    let (x, y) = position;
+
+    targets
+        .into_iter()
+        .filter_map(|handle| layout_of(handle).map(|layout| (handle, layout)))
+        .filter(|(_, layout)| {
+            let (left, top, _) = layout.origin;
+            let (width, height) = layout.available_space;
+            x >= left && x <= left + width && y >= top && y <= top + height
+        })
+        .max_by(|(_, a), (_, b)| {
+            let area = |layout: &Layout| layout.available_space.0 * layout.available_space.1;
+
+            a.origin.2.total_cmp(&b.origin.2).then(area(b).total_cmp(&area(a)))
+        })
+        .map(|(handle, _)| handle)
+}
+
 pub trait Widget<A, V, R>: std::any::Any
 where
     A: App<R>,
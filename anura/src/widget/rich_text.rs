@@ -0,0 +1,471 @@
+use unicode_linebreak::linebreaks;
+
+use crate::context::RenderContext;
+use crate::font::{align_line, is_whitespace_at, wrap_paragraph, Font, FontAtlas, GlyphData, HorizontalAlign, TextWrap};
+use crate::graphics::AnuraRenderCommand;
+use crate::{
+    app::App,
+    context::AnuraContext,
+    error::AnuraError,
+    event::{WidgetEvent},
+    graphics::AnuraRenderer,
+    prelude::{
+        DefaultMesh, DefaultVertex, FromAnuraMesh, FromAnuraUniform
+    },
+    view::View,
+    widget::Layout,
+};
+
+use super::Widget;
+
+/// How far italic glyphs lean to the right per pixel of height
+const ITALIC_SLANT: f32 = 0.2;
+
+#[derive(Clone, PartialEq, Default)]
+pub struct SpanStyle {
+    /// Falls back to `RichText::color`
+    pub color: Option<(f32, f32, f32, f32)>,
+    /// Overrides the size of the span's font
+    pub size: Option<f32>,
+    /// Falls back to `RichText::font`
+    pub font: Option<Font>,
+    /// Faux bold, the glyphs get drawn twice with a small offset
+    pub bold: bool,
+    /// Faux italic, the glyphs get slanted
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub background: Option<(f32, f32, f32, f32)>,
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+impl TextSpan {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: SpanStyle::default(),
+        }
+    }
+
+    pub fn color(mut self, color: (f32, f32, f32, f32)) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.style.size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: Font) -> Self {
+        self.style.font = Some(font);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.style.bold = true;
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.style.italic = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.style.underline = true;
+        self
+    }
+
+    pub fn strikethrough(mut self) -> Self {
+        self.style.strikethrough = true;
+        self
+    }
+
+    pub fn background(mut self, color: (f32, f32, f32, f32)) -> Self {
+        self.style.background = Some(color);
+        self
+    }
+}
+
+/// Text made out of differently styled spans which share lines
+pub struct RichText {
+    pub spans: Vec<TextSpan>,
+    /// Font of spans which dont set their own
+    pub font: Font,
+    /// Colour of spans which dont set their own
+    pub color: (f32, f32, f32, f32),
+    /// Distance between lines, the biggest font size on a line + 5px if not set
+    pub line_height: Option<f32>,
+    pub align: HorizontalAlign,
+    pub wrap: TextWrap,
+}
+
+impl Default for RichText {
+    fn default() -> Self {
+        Self {
+            spans: Vec::new(),
+            font: Font::Default,
+            color: (0.0, 0.0, 0.0, 1.0),
+            line_height: None,
+            align: HorizontalAlign::default(),
+            wrap: TextWrap::default(),
+        }
+    }
+}
+
+/// Glyph of a span, placed on a line
+struct SpanGlyph {
+    span: usize,
+    face: usize,
+    glyph_id: u16,
+    /// Pen position, the glyph offset is already included in `y` but not here
+    x: f32,
+    x_offset: f32,
+    y: f32,
+    advance: f32,
+}
+
+struct RichLine {
+    glyphs: Vec<SpanGlyph>,
+    top: f32,
+    baseline: f32,
+    height: f32,
+}
+
+impl RichText {
+    pub fn new(spans: Vec<TextSpan>) -> Self {
+        Self {
+            spans,
+            ..Default::default()
+        }
+    }
+
+    pub fn span_font(&self, span: &TextSpan) -> Font {
+        let font = span.style.font.as_ref().unwrap_or(&self.font);
+
+        match span.style.size {
+            Some(size) => font.with_size(size),
+            None => font.clone(),
+        }
+    }
+
+    /// Breaks the spans up into lines, `fonts` holds the atlas of every span
+    fn layout_spans<R: AnuraRenderer>(&self, fonts: &[&FontAtlas<R>], max_width: f32) -> Vec<RichLine> {
+        if self.spans.is_empty() { return Vec::new() }
+
+        // Pieces of spans making up each paragraph, plus the span its newline is in
+        // so empty lines still get a height
+        let mut paragraphs: Vec<(Vec<(usize, &str)>, usize)> = vec![(Vec::new(), 0)];
+
+        for (index, span) in self.spans.iter().enumerate() {
+            for (i, piece) in span.text.split('\n').enumerate() {
+                if i > 0 {
+                    paragraphs.push((Vec::new(), index));
+                }
+
+                let piece = piece.strip_suffix('\r').unwrap_or(piece);
+                let (pieces, last_span) = paragraphs.last_mut().unwrap();
+                *last_span = index;
+
+                if !piece.is_empty() {
+                    pieces.push((index, piece));
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut y = 0.0;
+
+        for (pieces, last_span) in paragraphs {
+            let text = pieces.iter().map(|(_, piece)| *piece).collect::<String>();
+
+            // Spans are shaped separately, theres no kerning across span borders
+            let mut shaped = Vec::new();
+            let mut owners = Vec::new();
+            let mut offset = 0;
+
+            for (span, piece) in &pieces {
+                for mut glyph in fonts[*span].shape(piece) {
+                    let whitespace = is_whitespace_at(piece, glyph.cluster);
+
+                    glyph.cluster += offset;
+                    shaped.push((glyph, whitespace));
+                    owners.push(*span);
+                }
+                offset += piece.len();
+            }
+
+            let break_opportunities = match self.wrap {
+                TextWrap::Word => linebreaks(&text).map(|(index, _)| index).collect::<Vec<_>>(),
+                _ => Vec::new(),
+            };
+
+            let starts = wrap_paragraph(&shaped, &break_opportunities, self.wrap, Some(max_width));
+
+            for (i, line_start) in starts.iter().enumerate() {
+                let line_end = starts.get(i + 1).copied().unwrap_or(shaped.len());
+                let last_in_paragraph = line_end == shaped.len();
+
+                let glyphs = &shaped[*line_start..line_end];
+
+                let font_size = owners[*line_start..line_end]
+                    .iter()
+                    .map(|span| fonts[*span].size)
+                    .fold(None, |max: Option<f32>, size| Some(max.map_or(size, |max| max.max(size))))
+                    .unwrap_or(fonts[last_span].size);
+
+                let height = self.line_height.unwrap_or(font_size + 5.0);
+                let baseline = y + font_size;
+
+                let aligned = align_line(glyphs, max_width, self.align, !last_in_paragraph);
+                let mut x = aligned.x;
+
+                let mut line = RichLine {
+                    glyphs: Vec::new(),
+                    top: y,
+                    baseline,
+                    height,
+                };
+
+                for (j, ((glyph, _), advance)) in glyphs.iter().zip(aligned.advances).enumerate() {
+                    line.glyphs.push(SpanGlyph {
+                        span: owners[line_start + j],
+                        face: glyph.face,
+                        glyph_id: glyph.glyph_id,
+                        x,
+                        x_offset: glyph.x_offset,
+                        y: baseline - glyph.y_offset,
+                        advance,
+                    });
+
+                    x += advance;
+                }
+
+                lines.push(line);
+                y += height;
+            }
+        }
+
+        lines
+    }
+}
+
+impl<A, V, R> Widget<A, V, R> for RichText
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+    R::Mesh: FromAnuraMesh,
+    R::Uniform: FromAnuraUniform,
+    R::RenderCommand: AnuraRenderCommand<Renderer = R>
+{
+    fn handle_event(
+        &mut self,
+        ctx: &mut AnuraContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        event: WidgetEvent,
+    ) -> Result<(), AnuraError<R::ErrorMessage>> {
+        match event {
+            // Spans can change at any point, already loaded fonts are skipped
+            WidgetEvent::Init | WidgetEvent::Update => {
+                for span in &self.spans {
+                    let font = self.span_font(span);
+                    ctx.app.font_manager.load_font(&font, false)?;
+                }
+            },
+            WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::ImePreedit { .. }
+            | WidgetEvent::ImeCommit(_)
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
+        };
+
+        Ok(())
+    }
+
+    fn generate_mesh(
+        &self,
+        ctx: &mut RenderContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        layout: Layout,
+    ) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>> {
+        let plain_shader = crate::graphics::PLAIN_SHADER;
+        let text_shader = crate::graphics::TEXT_SHADER;
+
+        let font_manager = &mut ctx.app.font_manager;
+
+        // Index of every span's font in the font manager
+        let span_fonts = self.spans
+            .iter()
+            .map(|span| font_manager.font_index(&self.span_font(span)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (x, y, z) = layout.origin;
+        let (max_width, max_height) = layout.available_space;
+
+        let lines = {
+            let fonts = span_fonts.iter().map(|index| &font_manager.fonts[*index]).collect::<Vec<_>>();
+            self.layout_spans(&fonts, max_width)
+        };
+
+        // Every atlas page needs its own command, keyed by (font, page)
+        let mut glyph_meshes: Vec<((usize, usize), DefaultMesh<DefaultVertex>)> = Vec::new();
+        let mut decorations = DefaultMesh::<DefaultVertex>::empty(z);
+
+        for line in lines {
+            // Same as TextOverflow::Clip
+            if line.top + line.height > max_height + 0.01 { break }
+
+            for glyph in &line.glyphs {
+                let style = &self.spans[glyph.span].style;
+                let font_index = span_fonts[glyph.span];
+                let font = &mut font_manager.fonts[font_index];
+                let color = style.color.unwrap_or(self.color);
+
+                if let Some(background) = style.background {
+                    decorations.merge(&mut DefaultMesh::quad(
+                        (x + glyph.x, y + line.top, z + 0.5),
+                        (glyph.advance, line.height),
+                        (0.0, 0.0, 0.0, 0.0),
+                        background,
+                    ));
+                }
+
+                let thickness = (font.size / 14.0).max(1.0);
+
+                if style.underline {
+                    decorations.merge(&mut DefaultMesh::quad(
+                        (x + glyph.x, y + line.baseline + font.size * 0.1, z + 1.1),
+                        (glyph.advance, thickness),
+                        (0.0, 0.0, 0.0, 0.0),
+                        color,
+                    ));
+                }
+
+                if style.strikethrough {
+                    decorations.merge(&mut DefaultMesh::quad(
+                        (x + glyph.x, y + line.baseline - font.size * 0.3, z + 1.1),
+                        (glyph.advance, thickness),
+                        (0.0, 0.0, 0.0, 0.0),
+                        color,
+                    ));
+                }
+
+                let GlyphData { uv, metrics, page } = match font.glyph(glyph.face, glyph.glyph_id) {
+                    Some(gd) => gd,
+                    None => continue,
+                };
+
+                let dim = (metrics.width as f32, metrics.height as f32);
+                if dim.0 <= 0.0 || dim.1 <= 0.0 { continue }
+
+                let pos = (
+                    x + glyph.x + glyph.x_offset + metrics.xmin as f32,
+                    y + glyph.y - (dim.1 + metrics.ymin as f32),
+                    z + 1.0,
+                );
+
+                let mut quad = DefaultMesh::quad(pos, dim, uv, color);
+
+                if style.italic {
+                    let baseline = y + glyph.y;
+                    for vert in &mut quad.verts {
+                        vert.pos[0] += (baseline - vert.pos[1]) * ITALIC_SLANT;
+                    }
+                }
+
+                let mesh = match glyph_meshes.iter().position(|(key, _)| *key == (font_index, page)) {
+                    Some(index) => &mut glyph_meshes[index].1,
+                    None => {
+                        glyph_meshes.push(((font_index, page), DefaultMesh::empty(z)));
+                        &mut glyph_meshes.last_mut().unwrap().1
+                    }
+                };
+
+                if style.bold {
+                    let mut offset = DefaultMesh {
+                        verts: quad.verts.clone(),
+                        inds: quad.inds.clone(),
+                        could_be_transparent: false,
+                        highest_z: quad.highest_z,
+                    };
+                    let shift = (font.size / 24.0).max(1.0);
+                    offset.verts.iter_mut().for_each(|vert| vert.pos[0] += shift);
+
+                    mesh.merge(&mut offset);
+                }
+
+                mesh.merge(&mut quad);
+            }
+        }
+
+        let mut commands = Vec::new();
+
+        if !decorations.verts.is_empty() {
+            decorations.possibly_trasparent();
+
+            let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", plain_shader, "fs_main")?;
+
+            let mut command = R::RenderCommand::new(R::Mesh::convert(&decorations), shader);
+            command.add_uniform(ctx.app.renderer.camera_uniform());
+            commands.push(command);
+        }
+
+        for ((font_index, _), _) in &glyph_meshes {
+            ctx.app.font_manager.fonts[*font_index].upload(&mut ctx.app.renderer);
+        }
+
+        for ((font_index, page), mut mesh) in glyph_meshes {
+            let texture = match ctx.app.font_manager.fonts[font_index].page_texture(page) {
+                Some(texture) => texture.clone(),
+                None => continue,
+            };
+            mesh.possibly_trasparent();
+
+            let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", text_shader, "fs_main")?;
+
+            let mut command = R::RenderCommand::new(R::Mesh::convert(&mesh), shader);
+            command.add_uniform(ctx.app.renderer.camera_uniform());
+            command.add_texture(texture);
+            commands.push(command);
+        }
+
+        Ok(commands)
+    }
+}
+
+#[cfg(all(test, feature = "default-font", feature = "wgpu-renderer"))]
+mod tests {
+    use super::*;
+    use crate::{font::FontManager, graphics::WgpuRenderer};
+
+    #[test]
+    fn span_size_applies_to_the_default_font() {
+        let mut fonts = FontManager::<WgpuRenderer>::new();
+        let rich_text = RichText::new(vec![TextSpan::new("Hello"), TextSpan::new("Hello").size(36.0)]);
+
+        let unsized_font = rich_text.span_font(&rich_text.spans[0]);
+        let sized_font = rich_text.span_font(&rich_text.spans[1]);
+        fonts.load_font(&unsized_font, false).unwrap();
+        fonts.load_font(&sized_font, false).unwrap();
+
+        let unsized_metrics = fonts.measure("Hello", &unsized_font, None).unwrap();
+        let sized_metrics = fonts.measure("Hello", &sized_font, None).unwrap();
+
+        assert_eq!(fonts.font_size(&sized_font).unwrap(), 36.0);
+        assert!(sized_metrics.size.0 > unsized_metrics.size.0);
+        assert!(sized_metrics.size.1 > unsized_metrics.size.1);
+    }
+}
@@ -1,8 +1,11 @@
 
 
 use crate::context::RenderContext;
-use crate::font::{Font, FontManager};
-use crate::graphics::AnuraRenderCommand;
+use crate::font::{
+    Font, FontAtlas, FontManager, HorizontalAlign, TextLayout, TextLayoutOptions, TextOverflow,
+    TextRenderMode, TextWrap, VerticalAlign,
+};
+use crate::graphics::{AnuraRenderCommand, AnuraVertex, SdfVertex};
 use crate::{
     app::App,
     context::AnuraContext,
@@ -18,11 +21,62 @@ use crate::{
 
 use super::Widget;
 
-#[derive(Default)]
 pub struct TextWidget {
     pub text: String,
     pub index: Option<usize>,
     pub font: Font,
+    pub color: (f32, f32, f32, f32),
+    /// Distance between baselines, font size + 5px if not set
+    pub line_height: Option<f32>,
+    pub align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    pub wrap: TextWrap,
+    pub overflow: TextOverflow,
+    /// How far the text is scrolled down when using `TextOverflow::Scroll`
+    pub scroll_offset: f32,
+    pub render_mode: TextRenderMode,
+}
+
+impl Default for TextWidget {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            index: None,
+            font: Font::Default,
+            color: (0.0, 0.0, 0.0, 1.0),
+            line_height: None,
+            align: HorizontalAlign::default(),
+            vertical_align: VerticalAlign::default(),
+            wrap: TextWrap::default(),
+            overflow: TextOverflow::default(),
+            scroll_offset: 0.0,
+            render_mode: TextRenderMode::default(),
+        }
+    }
+}
+
+impl TextWidget {
+    pub fn layout_options(&self, available_space: (f32, f32)) -> TextLayoutOptions {
+        TextLayoutOptions {
+            max_width: Some(available_space.0),
+            max_height: Some(available_space.1),
+            line_height: self.line_height,
+            align: self.align,
+            vertical_align: self.vertical_align,
+            wrap: self.wrap,
+            overflow: self.overflow,
+            scroll_offset: self.scroll_offset,
+        }
+    }
+    /// Lays the text out like it gets rendered, for hit testing and measuring.
+    /// Positions are relative to the widget's origin
+    pub fn layout<R: AnuraRenderer>(
+        &self,
+        font_manager: &FontManager<R>,
+        available_space: (f32, f32),
+    ) -> Result<TextLayout, AnuraError<R::ErrorMessage>> {
+        font_manager.layout(&self.font, &self.text, &self.layout_options(available_space))
+    }
 }
 
 impl<A, V, R> Widget<A, V, R> for TextWidget
@@ -42,10 +96,25 @@ where
         event: WidgetEvent,
     ) -> Result<(), AnuraError<R::ErrorMessage>> {
         match event {
-            WidgetEvent::Init => {
-                ctx.app.font_manager.load_font(&mut ctx.app.renderer, &self.font, false);
+            // The render mode can change at any point, already loaded fonts are skipped
+            WidgetEvent::Init | WidgetEvent::Update => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+
+                if let TextRenderMode::Sdf(_) = self.render_mode {
+                    ctx.app.font_manager.load_sdf_font(&self.font)?;
+                }
             },
-            WidgetEvent::Update => {}
+            WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::ImePreedit { .. }
+            | WidgetEvent::ImeCommit(_)
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
         };
 
         Ok(())
@@ -59,27 +128,82 @@ where
     ) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>>
     where     
     {
-        let vert_code = crate::graphics::PLAIN_SHADER;
-        let frag_code = crate::graphics::IMAGE_SHADER;
+        let options = self.layout_options(layout.available_space);
+
+        match self.render_mode {
+            TextRenderMode::Bitmap => {
+                let font = ctx.app.font_manager.get_font_mut(&self.font)?;
+
+                let text_layout = TextLayout::new(font, &self.text, &options);
+
+                let meshes = DefaultMesh::<DefaultVertex>::text_layout(
+                    layout.origin,
+                    &text_layout,
+                    font,
+                    self.color,
+                );
 
-        let shader = ctx.app.renderer.load_shader(vert_code, "vs_main", frag_code, "fs_main");
+                let vert_code = crate::graphics::PLAIN_SHADER;
+                let frag_code = crate::graphics::TEXT_SHADER;
 
-        let font = ctx.app.font_manager.get_font(&self.font).expect("Font not loaded");
+                atlas_commands(&mut ctx.app.renderer, font, meshes, vert_code, frag_code)
+            }
+            TextRenderMode::Sdf(effects) => {
+                //The sdf atlas is shared by every size so the layout needs the real one
+                let size = ctx.app.font_manager.font_size(&self.font)?;
+                let font = ctx.app.font_manager.get_sdf_font_mut(&self.font)?;
 
-        let mut mesh = DefaultMesh::<DefaultVertex>::bounded_text(
-            layout.origin, 
-            Some(layout.available_space), 
-            &self.text, 
-            &font
-        );
-        mesh.possibly_trasparent();
+                let text_layout = TextLayout::with_size(font, size, &self.text, &options);
+
+                let meshes = DefaultMesh::<SdfVertex>::sdf_text_layout(
+                    layout.origin,
+                    &text_layout,
+                    font,
+                    self.color,
+                    &effects,
+                );
+
+                let shader_code = crate::graphics::SDF_SHADER;
+
+                atlas_commands(&mut ctx.app.renderer, font, meshes, shader_code, shader_code)
+            }
+        }
+    }
+}
+
+/// Uploads the atlas and makes one command per atlas page the glyphs ended up on
+pub(crate) fn atlas_commands<R, T>(
+    renderer: &mut R,
+    font: &mut FontAtlas<R>,
+    meshes: Vec<(usize, DefaultMesh<T>)>,
+    vert_code: &str,
+    frag_code: &str,
+) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>>
+where
+    R: AnuraRenderer,
+    R::Mesh: FromAnuraMesh,
+    R::RenderCommand: AnuraRenderCommand<Renderer = R>,
+    T: AnuraVertex,
+{
+    font.upload(renderer);
+
+    let mut commands = Vec::new();
+    for (page, mesh) in meshes {
+        let texture = match font.page_texture(page) {
+            Some(texture) => texture.clone(),
+            None => continue,
+        };
+
+        let shader = renderer.load_shader(vert_code, "vs_main", frag_code, "fs_main")?;
 
         let mut command = R::RenderCommand::new(R::Mesh::convert(&mesh), shader);
 
-        let camera_uniform = ctx.app.renderer.camera_uniform();
+        let camera_uniform = renderer.camera_uniform();
         command.add_uniform(camera_uniform);
-        command.add_texture(font.texture_handle.clone());
+        command.add_texture(texture);
 
-        Ok(vec![command])
+        commands.push(command);
     }
+
+    Ok(commands)
 }
\ No newline at end of file
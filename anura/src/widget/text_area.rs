@@ -0,0 +1,1112 @@
+use std::ops::Range;
+
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::{
+    app::App,
+    clipboard::Clipboard,
+    context::{AnuraContext, RenderContext},
+    error::AnuraError,
+    event::WidgetEvent,
+    font::{Font, FontAtlas, LayoutLine, PositionedGlyph, TextLayout, TextLayoutOptions, TextWrap},
+    graphics::{AnuraRenderCommand, AnuraRenderer},
+    handle::NodeType,
+    prelude::{DefaultMesh, DefaultVertex, FromAnuraMesh, FromAnuraUniform},
+    view::View,
+    widget::Layout,
+};
+
+use super::{
+    atlas_commands, clamp_index, is_private_use, line_at, next_grapheme, next_word,
+    prev_grapheme, prev_word, word_at, Edit, EditHistory, EditKind, EditState, Widget,
+};
+
+/// Multiple lines of editable text with undo/redo
+///
+/// Every line of the text is laid out on its own and only lines which changed get
+/// laid out again, only the lines in view are turned into meshes which are kept
+/// until the line changes or scrolls
+pub struct TextArea {
+    pub text: String,
+    pub font: Font,
+    pub color: (f32, f32, f32, f32),
+    pub selection_color: (f32, f32, f32, f32),
+    pub background: Option<(f32, f32, f32, f32)>,
+    /// Space between the edges and the text
+    pub padding: f32,
+    /// Lines are scrolled horizontally instead of being wrapped with `TextWrap::None`
+    pub wrap: TextWrap,
+    /// Distance between baselines, font size + 5px if not set
+    pub line_height: Option<f32>,
+    /// Byte index of the caret, see `select` for moving it
+    pub caret: usize,
+    /// Where the selection started, same as `caret` if nothing is selected
+    pub anchor: usize,
+    pub history: EditHistory,
+    pub state: TextAreaState,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            font: Font::Default,
+            color: (0.0, 0.0, 0.0, 1.0),
+            selection_color: (0.6, 0.75, 1.0, 1.0),
+            background: Some((1.0, 1.0, 1.0, 1.0)),
+            padding: 4.0,
+            wrap: TextWrap::Word,
+            line_height: None,
+            caret: 0,
+            anchor: 0,
+            history: EditHistory::default(),
+            state: TextAreaState::default(),
+        }
+    }
+}
+
+/// A line of the text (in between newlines) with its own layout
+#[derive(Debug, Clone)]
+struct Paragraph {
+    /// Byte index of the paragraph in the text
+    start: usize,
+    /// Length in bytes without the newline
+    len: usize,
+    /// Distance from the top of the text
+    top: f32,
+    layout: TextLayout,
+    /// An edit touched the paragraph since it was laid out
+    dirty: bool,
+    /// Glyphs of the lines in view, see `TextArea::update_meshes`
+    meshes: Option<ParagraphMeshes>,
+}
+
+impl Paragraph {
+    /// Placeholder for a paragraph which still has to be laid out
+    fn dirty(len: usize) -> Self {
+        Self {
+            start: 0,
+            len,
+            top: 0.0,
+            layout: TextLayout::default(),
+            dirty: true,
+            meshes: None,
+        }
+    }
+
+    fn height(&self) -> f32 {
+        self.layout.lines.len() as f32 * self.layout.line_height
+    }
+}
+
+/// What the glyph meshes of a paragraph were built for
+#[derive(Debug, Clone, PartialEq)]
+struct MeshKey {
+    /// Lines which were in view
+    lines: Range<usize>,
+    scroll_x: f32,
+    width: f32,
+    color: (f32, f32, f32, f32),
+    /// See `FontAtlas::generation`
+    generation: u64,
+}
+
+/// Glyph meshes per atlas page, relative to the top of the paragraph and the left edge of the text
+#[derive(Debug, Clone)]
+struct ParagraphMeshes {
+    key: MeshKey,
+    meshes: Vec<(usize, DefaultMesh<DefaultVertex>)>,
+}
+
+/// What the paragraphs were laid out with, changing any of it lays out everything again
+#[derive(Debug, Clone, PartialEq)]
+struct ParagraphKey {
+    font: Font,
+    width: f32,
+    wrap: TextWrap,
+    line_height: Option<f32>,
+}
+
+#[derive(Default)]
+pub struct TextAreaState {
+    edit: EditState,
+    paragraphs: Vec<Paragraph>,
+    /// The text the paragraphs belong to, edits through the text area are applied to both.
+    /// If they differ the text got changed from the outside and everything is laid out again
+    text: String,
+    key: Option<ParagraphKey>,
+    /// Where the caret wants to be horizontally while moving up and down,
+    /// so going through a short line doesnt lose the column
+    goal_x: Option<f32>,
+}
+
+impl TextArea {
+    /// Byte range of the selected text, empty if nothing is selected
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Selects from `anchor` to `caret`, both are byte indices and get clamped to the text
+    pub fn select(&mut self, anchor: usize, caret: usize) {
+        self.anchor = clamp_index(&self.text, anchor);
+        self.caret = clamp_index(&self.text, caret);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0, self.text.len());
+    }
+
+    /// Replaces the selection with `text` as if it was typed
+    pub fn insert(&mut self, text: &str) -> bool {
+        let text = text
+            .chars()
+            .filter(|c| *c == '\n' || (!c.is_control() && !is_private_use(*c)))
+            .collect::<String>();
+
+        let kind = match self.selection().is_empty() {
+            true => EditKind::Typing,
+            false => EditKind::Other,
+        };
+
+        self.replace(self.selection(), &text, kind)
+    }
+
+    /// Copies the selection, returns false if nothing is selected
+    pub fn copy(&self, clipboard: &mut dyn Clipboard) -> bool {
+        if self.selection().is_empty() { return false }
+
+        clipboard.set_text(self.selected_text());
+        true
+    }
+
+    /// Copies the selection and deletes it, returns true if the text changed
+    pub fn cut(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        if !self.copy(clipboard) { return false }
+
+        self.replace(self.selection(), "", EditKind::Other)
+    }
+
+    /// Replaces the selection with the clipboard's text, undone as a single step
+    pub fn paste(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        let text = match clipboard.get_text() {
+            Some(text) => text,
+            None => return false,
+        };
+
+        let text = text
+            .chars()
+            .filter(|c| *c == '\n' || (!c.is_control() && !is_private_use(*c)))
+            .collect::<String>();
+
+        self.replace(self.selection(), &text, EditKind::Other)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let (range, restored) = match self.history.next_undo() {
+            Some(edit) => (edit.at..edit.at + edit.inserted.len(), edit.removed.clone()),
+            None => return false,
+        };
+
+        match self.history.undo(&mut self.text) {
+            Some((caret, anchor)) => {
+                self.text_replaced(range, &restored);
+                self.select(anchor, caret);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let (range, restored) = match self.history.next_redo() {
+            Some(edit) => (edit.at..edit.at + edit.removed.len(), edit.inserted.clone()),
+            None => return false,
+        };
+
+        match self.history.redo(&mut self.text) {
+            Some((caret, anchor)) => {
+                self.text_replaced(range, &restored);
+                self.select(anchor, caret);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn replace(&mut self, range: Range<usize>, insert: &str, kind: EditKind) -> bool {
+        if range.is_empty() && insert.is_empty() { return false }
+
+        let removed = self.text[range.clone()].to_owned();
+        let edit = Edit::new(range.start, removed, insert.to_owned(), (self.caret, self.anchor), kind, self.state.edit.now);
+
+        self.text.replace_range(range.clone(), insert);
+        self.text_replaced(range.clone(), insert);
+        self.caret = range.start + insert.len();
+        self.anchor = self.caret;
+
+        self.history.record(edit);
+        true
+    }
+
+    /// Marks the paragraphs an edit touched as dirty, called after `range` of the text
+    /// got replaced with `inserted`
+    fn text_replaced(&mut self, range: Range<usize>, inserted: &str) {
+        let state = &mut self.state;
+
+        // Outside changes happened in between, everything gets laid out again anyway
+        if state.paragraphs.is_empty() || state.text.get(range.clone()).is_none() { return }
+        state.text.replace_range(range.clone(), inserted);
+
+        let first = paragraph_of(&state.paragraphs, range.start);
+        let last = paragraph_of(&state.paragraphs, range.end);
+
+        // The edit might have added or removed newlines, so the paragraphs are split up again
+        let start = state.paragraphs[first].start;
+        let last_end = state.paragraphs[last].start + state.paragraphs[last].len;
+
+        let replaced = match (last_end + inserted.len()).checked_sub(range.len()) {
+            Some(end) if last_end >= range.end => self.text.get(start..end),
+            _ => None,
+        };
+
+        let replaced = match replaced {
+            Some(replaced) => replaced,
+            // The paragraphs were out of sync, lay everything out again
+            None => {
+                state.key = None;
+                return;
+            }
+        };
+
+        state.paragraphs.splice(first..=last, replaced.split('\n').map(|line| Paragraph::dirty(line.len())));
+
+        // Later edits in the same frame look paragraphs up by their start
+        let mut start = start;
+        for paragraph in &mut state.paragraphs[first..] {
+            paragraph.start = start;
+            start += paragraph.len + 1;
+        }
+    }
+
+    /// Deletes the selection, or the text between the caret and `to` if nothing is selected
+    fn delete_towards(&mut self, to: usize) -> bool {
+        match self.selection() {
+            selection if !selection.is_empty() => self.replace(selection, "", EditKind::Other),
+            _ => self.replace(self.caret.min(to)..self.caret.max(to), "", EditKind::Deleting),
+        }
+    }
+
+    fn move_caret(&mut self, to: usize, select: bool) {
+        self.caret = to;
+        if !select {
+            self.anchor = to;
+        }
+    }
+
+    fn layout_options(&self, width: f32) -> TextLayoutOptions {
+        TextLayoutOptions {
+            max_width: match self.wrap {
+                TextWrap::None => None,
+                _ => Some(width),
+            },
+            line_height: self.line_height,
+            wrap: self.wrap,
+            ..Default::default()
+        }
+    }
+
+    fn paragraph_key(&self, width: f32) -> ParagraphKey {
+        ParagraphKey {
+            font: self.font.clone(),
+            width,
+            wrap: self.wrap,
+            line_height: self.line_height,
+        }
+    }
+
+    /// The cached paragraphs if they still match the text and settings
+    fn cached_paragraphs(&self, key: &ParagraphKey) -> Option<&[Paragraph]> {
+        let state = &self.state;
+
+        let up_to_date = state.key.as_ref() == Some(key)
+            && state.text == self.text
+            && !state.paragraphs.iter().any(|paragraph| paragraph.dirty);
+
+        up_to_date.then_some(state.paragraphs.as_slice())
+    }
+
+    /// Lays out the paragraphs which changed, returns true if any did
+    fn update_paragraphs<R: AnuraRenderer>(&mut self, font: &FontAtlas<R>, width: f32) -> bool {
+        let key = self.paragraph_key(width);
+        let options = self.layout_options(width);
+        let state = &mut self.state;
+
+        if state.key.as_ref() != Some(&key) || state.text != self.text {
+            state.paragraphs = layout_paragraphs(font, &self.text, &options);
+            state.text = self.text.clone();
+            state.key = Some(key);
+            return true;
+        }
+
+        if !state.paragraphs.iter().any(|paragraph| paragraph.dirty) { return false }
+
+        // Paragraphs after an edit only move
+        let (mut start, mut top) = (0, 0.0);
+
+        for paragraph in &mut state.paragraphs {
+            paragraph.start = start;
+            paragraph.top = top;
+
+            if paragraph.dirty {
+                paragraph.layout = TextLayout::new(font, &self.text[start..start + paragraph.len], &options);
+                paragraph.dirty = false;
+                paragraph.meshes = None;
+            }
+
+            start += paragraph.len + 1;
+            top += paragraph.height();
+        }
+
+        true
+    }
+
+    /// Builds the glyph meshes of the paragraphs in view which don't have up to date ones,
+    /// `generate_mesh` only moves them into place
+    fn update_meshes<R: AnuraRenderer>(&mut self, font: &mut FontAtlas<R>, inner: (f32, f32)) {
+        let scroll = self.state.edit.scroll;
+        let line_height = self.line_height();
+
+        for paragraph in &mut self.state.paragraphs {
+            let lines = lines_in_view(paragraph, scroll.1, inner.1, font.size, line_height);
+            if lines.is_empty() {
+                paragraph.meshes = None;
+                continue;
+            }
+
+            let key = MeshKey {
+                lines,
+                scroll_x: scroll.0,
+                width: inner.0,
+                color: self.color,
+                generation: font.generation(),
+            };
+
+            if paragraph.meshes.as_ref().is_some_and(|meshes| meshes.key == key) { continue }
+
+            let meshes = paragraph_meshes(paragraph, &key, font);
+            paragraph.meshes = Some(ParagraphMeshes { key, meshes });
+        }
+    }
+
+    fn line_height(&self) -> f32 {
+        self.state.paragraphs.first().map_or(0.0, |paragraph| paragraph.layout.line_height)
+    }
+
+    /// Start and end of the wrapped line the caret is on
+    fn visual_line(&self) -> Range<usize> {
+        let paragraphs = &self.state.paragraphs;
+        let paragraph = &paragraphs[paragraph_of(paragraphs, self.caret)];
+        let (_, top) = paragraph.layout.index_to_position(self.caret - paragraph.start);
+
+        let line = match paragraph.layout.line_at(top) {
+            Some(line) => &paragraph.layout.lines[line],
+            None => return paragraph.start..paragraph.start,
+        };
+
+        let start = paragraph.start + line.text.start;
+        let end = paragraph.start + line.text.end;
+
+        // The end of a wrapped line is already on the next line, stop in front of the last character
+        let wrapped = paragraph.layout.lines.iter().any(|other| other.text.start == line.text.end);
+        match wrapped {
+            true => start..prev_grapheme(&self.text, end).max(start),
+            false => start..end,
+        }
+    }
+
+    /// Moves the caret `lines` wrapped lines up (negative) or down, keeping its column
+    fn move_vertically(&mut self, lines: f32, select: bool) {
+        let paragraphs = &self.state.paragraphs;
+        let line_height = self.line_height();
+
+        let (x, top) = index_to_point(paragraphs, self.caret);
+        let goal_x = *self.state.goal_x.get_or_insert(x);
+
+        let target = top + line_height * (lines + 0.5);
+        let index = if target < 0.0 {
+            0
+        } else if target >= content_size(paragraphs).1 {
+            self.text.len()
+        } else {
+            point_to_index(paragraphs, (goal_x, target))
+        };
+
+        self.move_caret(index, select);
+    }
+
+    fn handle_keys<R: AnuraRenderer>(
+        &mut self,
+        font: &FontAtlas<R>,
+        keys: &[VirtualKeyCode],
+        (shift, ctrl, command): (bool, bool, bool),
+        (width, height): (f32, f32),
+        clipboard: &mut dyn Clipboard,
+    ) -> bool {
+        let mut changed = false;
+
+        for key in keys {
+            let (text, caret) = (&self.text, self.caret);
+
+            let prev = if ctrl { prev_word(text, caret) } else { prev_grapheme(text, caret) };
+            let next = if ctrl { next_word(text, caret) } else { next_grapheme(text, caret) };
+            let selection = self.selection();
+            let page = (height / self.line_height().max(1.0)).floor().max(1.0);
+
+            if !matches!(key, VirtualKeyCode::Up | VirtualKeyCode::Down | VirtualKeyCode::PageUp | VirtualKeyCode::PageDown) {
+                self.state.goal_x = None;
+            }
+
+            match key {
+                // Without shift the selection collapses to the side the caret moved to
+                VirtualKeyCode::Left if !shift && !selection.is_empty() => self.move_caret(selection.start, false),
+                VirtualKeyCode::Right if !shift && !selection.is_empty() => self.move_caret(selection.end, false),
+                VirtualKeyCode::Left => self.move_caret(prev, shift),
+                VirtualKeyCode::Right => self.move_caret(next, shift),
+                VirtualKeyCode::Up => self.move_vertically(-1.0, shift),
+                VirtualKeyCode::Down => self.move_vertically(1.0, shift),
+                VirtualKeyCode::PageUp => self.move_vertically(-page, shift),
+                VirtualKeyCode::PageDown => self.move_vertically(page, shift),
+                VirtualKeyCode::Home if ctrl => self.move_caret(0, shift),
+                VirtualKeyCode::End if ctrl => self.move_caret(self.text.len(), shift),
+                VirtualKeyCode::Home => self.move_caret(self.visual_line().start, shift),
+                VirtualKeyCode::End => self.move_caret(self.visual_line().end, shift),
+                VirtualKeyCode::A if command => self.select_all(),
+                VirtualKeyCode::C if command => { self.copy(clipboard); }
+                VirtualKeyCode::X if command => { self.cut(clipboard); }
+                VirtualKeyCode::V if command => { self.paste(clipboard); }
+                VirtualKeyCode::Z if command && shift => { self.redo(); }
+                VirtualKeyCode::Z if command => { self.undo(); }
+                VirtualKeyCode::Y if command => { self.redo(); }
+                VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => { self.insert("\n"); }
+                VirtualKeyCode::Back => { self.delete_towards(prev); }
+                VirtualKeyCode::Delete => { self.delete_towards(next); }
+                _ => continue,
+            }
+
+            // Later keys of the same frame need to see the new text
+            self.update_paragraphs(font, width);
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Scrolls just far enough for the caret to be in view
+    fn scroll_to_caret(&mut self, (width, height): (f32, f32)) {
+        let (x, top) = index_to_point(&self.state.paragraphs, self.caret);
+        let line_height = self.line_height();
+        let scroll = &mut self.state.edit.scroll;
+
+        if top < scroll.1 {
+            scroll.1 = top;
+        } else if top + line_height > scroll.1 + height {
+            scroll.1 = top + line_height - height;
+        }
+
+        if x < scroll.0 {
+            scroll.0 = x;
+        } else if x > scroll.0 + width {
+            scroll.0 = x - width;
+        }
+    }
+
+    fn clamp_scroll(&mut self, (width, height): (f32, f32)) {
+        let (content_width, content_height) = content_size(&self.state.paragraphs);
+        let scroll = &mut self.state.edit.scroll;
+
+        scroll.0 = scroll.0.min(content_width - width).max(0.0);
+        scroll.1 = scroll.1.min(content_height - height).max(0.0);
+    }
+}
+
+impl<A, V, R> Widget<A, V, R> for TextArea
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+    R::Mesh: FromAnuraMesh,
+    R::Uniform: FromAnuraUniform,
+    R::RenderCommand: AnuraRenderCommand<Renderer = R>
+{
+    fn handle_event(
+        &mut self,
+        ctx: &mut AnuraContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        event: WidgetEvent,
+    ) -> Result<(), AnuraError<R::ErrorMessage>> {
+        let now = ctx.app.input_manager.now();
+        self.state.edit.now = now;
+
+        match event {
+            WidgetEvent::Init => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+            }
+            WidgetEvent::Update => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+
+                // The text might have been changed from the outside
+                self.caret = clamp_index(&self.text, self.caret);
+                self.anchor = clamp_index(&self.text, self.anchor);
+
+                // Nothing is known about the size before the first render
+                let layout = match ctx.layout() {
+                    Some(layout) => layout,
+                    None => return Ok(()),
+                };
+
+                let (x, y, _) = layout.origin;
+                let (width, height) = layout.available_space;
+                let inner = ((width - self.padding * 2.0).max(0.0), (height - self.padding * 2.0).max(0.0));
+
+                let mut changed = self.update_paragraphs(ctx.app.font_manager.get_font(&self.font)?, inner.0);
+                let mut moved = false;
+
+                let input = &ctx.app.input_manager;
+                let mouse = (input.mouse_position.x as f32, input.mouse_position.y as f32);
+                let hovered = mouse.0 >= x && mouse.0 <= x + width && mouse.1 >= y && mouse.1 <= y + height;
+                let (shift, ctrl, command) = (input.shift(), input.ctrl(), input.command());
+                let just_pressed = input.just_pressed(MouseButton::Left);
+                let mouse_down = input.is_down(MouseButton::Left);
+                let clicks = input.click_count(MouseButton::Left);
+                let scroll_delta = input.scroll_delta;
+
+                if just_pressed {
+                    if hovered {
+                        ctx.focus();
+                    } else if ctx.is_focused() {
+                        ctx.ui_tree.blur();
+                    }
+                }
+
+                let focused = ctx.is_focused();
+                changed |= self.state.edit.update_focus(focused);
+
+                let scroll = self.state.edit.scroll;
+                let text_point = (
+                    mouse.0 - x - self.padding + scroll.0,
+                    mouse.1 - y - self.padding + scroll.1,
+                );
+
+                if just_pressed && hovered {
+                    let index = point_to_index(&self.state.paragraphs, text_point);
+                    self.state.goal_x = None;
+
+                    match clicks {
+                        1 => {
+                            self.move_caret(index, shift);
+                            self.state.edit.dragging = true;
+                        }
+                        2 => {
+                            let word = word_at(&self.text, index);
+                            self.select(word.start, word.end);
+                        }
+                        _ => {
+                            let line = line_at(&self.text, index);
+                            self.select(line.start, line.end);
+                        }
+                    }
+
+                    moved = true;
+                } else if self.state.edit.dragging && mouse_down {
+                    let index = point_to_index(&self.state.paragraphs, text_point);
+
+                    if index != self.caret {
+                        self.move_caret(index, true);
+                        moved = true;
+                    }
+                }
+
+                if !mouse_down {
+                    self.state.edit.dragging = false;
+                }
+
+                // Keys are meant for the ime while its composing
+                if focused && self.state.edit.preedit.is_none() {
+                    let font = ctx.app.font_manager.get_font(&self.font)?;
+
+                    let keys = ctx.app.input_manager.key_presses.clone();
+                    moved |= self.handle_keys(font, &keys, (shift, ctrl, command), inner, &mut *ctx.app.clipboard);
+
+                    let typed = ctx.app.input_manager.received_chars.iter().collect::<String>();
+                    if !typed.is_empty() && self.insert(&typed) {
+                        self.update_paragraphs(font, inner.0);
+                        self.state.goal_x = None;
+                        moved = true;
+                    }
+                }
+
+                if moved {
+                    self.state.edit.reset_blink(now);
+                    self.scroll_to_caret(inner);
+                }
+
+                if hovered && scroll_delta != (0.0, 0.0) {
+                    self.state.edit.scroll.0 -= scroll_delta.0;
+                    self.state.edit.scroll.1 -= scroll_delta.1;
+                    changed = true;
+                }
+
+                self.clamp_scroll(inner);
+
+                if focused {
+                    changed |= self.state.edit.update_blink(now);
+
+                    let (caret_x, top) = index_to_point(&self.state.paragraphs, self.caret);
+                    let scroll = self.state.edit.scroll;
+                    ctx.app.request_ime((
+                        x + self.padding + caret_x - scroll.0,
+                        y + self.padding + top - scroll.1 + self.line_height(),
+                    ));
+                }
+
+                self.update_meshes(ctx.app.font_manager.get_font_mut(&self.font)?, inner);
+
+                if changed || moved {
+                    ctx.issue_rerender();
+                }
+            }
+            WidgetEvent::ImePreedit { text, cursor } => {
+                self.state.edit.set_preedit(text, cursor);
+                ctx.issue_rerender();
+            }
+            WidgetEvent::ImeCommit(text) => {
+                if !self.insert(&text) { return Ok(()) }
+
+                self.state.goal_x = None;
+                self.state.edit.reset_blink(now);
+
+                if let Some(layout) = ctx.layout() {
+                    let (width, height) = layout.available_space;
+                    let inner = ((width - self.padding * 2.0).max(0.0), (height - self.padding * 2.0).max(0.0));
+
+                    self.update_paragraphs(ctx.app.font_manager.get_font(&self.font)?, inner.0);
+                    self.scroll_to_caret(inner);
+                }
+                ctx.issue_rerender();
+            }
+            WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
+        };
+
+        Ok(())
+    }
+
+    fn generate_mesh(
+        &self,
+        ctx: &mut RenderContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        layout: Layout,
+    ) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>> {
+        let plain_shader = crate::graphics::PLAIN_SHADER;
+        let text_shader = crate::graphics::TEXT_SHADER;
+
+        let (x, y, z) = layout.origin;
+        let (width, height) = layout.available_space;
+        let inner = ((width - self.padding * 2.0).max(0.0), (height - self.padding * 2.0).max(0.0));
+        let origin = (x + self.padding, y + self.padding);
+
+        let focused = match ctx.current {
+            NodeType::Handle(handle) => ctx.ui_tree.is_focused(&handle),
+            NodeType::Root => false,
+        };
+
+        let font = ctx.app.font_manager.get_font_mut(&self.font)?;
+
+        // Only happens when rendering before the first update or right after a resize
+        let fresh;
+        let paragraphs = match self.cached_paragraphs(&self.paragraph_key(inner.0)) {
+            Some(paragraphs) => paragraphs,
+            None => {
+                fresh = layout_paragraphs(font, &self.text, &self.layout_options(inner.0));
+                fresh.as_slice()
+            }
+        };
+
+        let size = font.size;
+        let line_height = paragraphs.first().map_or(size + 5.0, |paragraph| paragraph.layout.line_height);
+        let scroll = self.state.edit.scroll;
+        let selection = self.selection();
+
+        // Lines are only drawn if they are completely inside
+        let in_view = |top: f32| top - scroll.1 >= -0.01 && top - scroll.1 + line_height <= inner.1 + 0.01;
+
+        let mut quads = DefaultMesh::<DefaultVertex>::empty(z);
+
+        if let Some(background) = self.background {
+            quads.merge(&mut DefaultMesh::quad((x, y, z), (width, height), (0.0, 0.0, 0.0, 0.0), background));
+        }
+
+        // Glyph meshes of every paragraph in view, moved to where they end up on screen
+        let mut meshes: Vec<(usize, DefaultMesh<DefaultVertex>)> = Vec::new();
+
+        for paragraph in paragraphs.iter() {
+            if paragraph.top - scroll.1 > inner.1 { break }
+
+            let lines = lines_in_view(paragraph, scroll.1, inner.1, size, line_height);
+            if lines.is_empty() { continue }
+
+            let key = MeshKey {
+                lines: lines.clone(),
+                scroll_x: scroll.0,
+                width: inner.0,
+                color: self.color,
+                generation: font.generation(),
+            };
+
+            let paragraph_meshes = match &paragraph.meshes {
+                Some(cached) if cached.key == key => {
+                    // Glyphs only count as used when they are looked up
+                    for (page, _) in &cached.meshes {
+                        font.atlas.mark_used(*page);
+                    }
+                    cached.meshes.clone()
+                }
+                _ => paragraph_meshes(paragraph, &key, font),
+            };
+
+            for (page, mut mesh) in paragraph_meshes {
+                mesh.translate((origin.0, origin.1 + paragraph.top - scroll.1, z));
+
+                match meshes.iter_mut().find(|(p, _)| *p == page) {
+                    Some((_, merged)) => merged.merge(&mut mesh),
+                    None => meshes.push((page, mesh)),
+                }
+            }
+
+            if !focused || selection.is_empty() { continue }
+
+            let paragraph_layout = &paragraph.layout;
+
+            for line in &paragraph_layout.lines[lines] {
+                let top = paragraph.top + line.baseline - size;
+                let glyphs = &paragraph_layout.glyphs[line.glyphs.clone()];
+
+                let line_text = paragraph.start + line.text.start..paragraph.start + line.text.end;
+                if selection.start > line_text.end || selection.end < line_text.start { continue }
+
+                let line_end_x = glyphs.last().map_or(line.x, |glyph| glyph.x + glyph.advance);
+                let x_of = |index: usize| match index < line_text.end {
+                    true => paragraph_layout.index_to_position(index - paragraph.start).0,
+                    false => line_end_x,
+                };
+
+                let start_x = x_of(selection.start.max(line_text.start));
+                // Selected newlines show up as a bit of space after the line
+                let end_x = match selection.end > line_text.end {
+                    true => line_end_x + size / 3.0,
+                    false => x_of(selection.end),
+                };
+
+                let start_x = (start_x - scroll.0).clamp(0.0, inner.0);
+                let end_x = (end_x - scroll.0).clamp(0.0, inner.0);
+
+                if end_x > start_x {
+                    quads.merge(&mut DefaultMesh::quad(
+                        (origin.0 + start_x, origin.1 + top - scroll.1, z + 0.5),
+                        (end_x - start_x, line_height),
+                        (0.0, 0.0, 0.0, 0.0),
+                        self.selection_color,
+                    ));
+                }
+            }
+        }
+
+        let thickness = (size / 16.0).max(1.0);
+        let preedit = self.state.edit.preedit.as_ref().filter(|_| focused);
+
+        if focused && self.state.edit.caret_visible && !paragraphs.is_empty() && preedit.is_none() {
+            let (caret_x, top) = index_to_point(paragraphs, self.caret);
+            let caret_x = caret_x - scroll.0;
+
+            if in_view(top) && caret_x >= 0.0 && caret_x <= inner.0 {
+                quads.merge(&mut DefaultMesh::quad(
+                    (origin.0 + caret_x, origin.1 + top - scroll.1, z + 1.5),
+                    (thickness, line_height),
+                    (0.0, 0.0, 0.0, 0.0),
+                    self.color,
+                ));
+            }
+        }
+
+        // The composition is drawn on top of the text at the caret instead of
+        // pushing the text after it aside, that would mean laying the paragraph out again
+        if let (Some(preedit), false) = (preedit, paragraphs.is_empty()) {
+            let (caret_x, top) = index_to_point(paragraphs, self.caret);
+            let preedit_origin = (origin.0 + caret_x - scroll.0, origin.1 + top - scroll.1);
+
+            let options = TextLayoutOptions {
+                line_height: self.line_height,
+                wrap: TextWrap::None,
+                ..Default::default()
+            };
+            let preedit_layout = TextLayout::new(font, &preedit.text, &options);
+            let preedit_width = preedit_layout.size.0;
+
+            quads.merge(&mut DefaultMesh::quad(
+                (preedit_origin.0, preedit_origin.1, z + 2.0),
+                (preedit_width, line_height),
+                (0.0, 0.0, 0.0, 0.0),
+                self.background.unwrap_or((1.0, 1.0, 1.0, 1.0)),
+            ));
+
+            if let Some(line) = preedit_layout.lines.first() {
+                quads.merge(&mut DefaultMesh::quad(
+                    (preedit_origin.0, preedit_origin.1 + line.baseline + thickness, z + 2.5),
+                    (preedit_width, thickness),
+                    (0.0, 0.0, 0.0, 0.0),
+                    self.color,
+                ));
+            }
+
+            if let (true, Some(caret)) = (self.state.edit.caret_visible, preedit.caret()) {
+                let (caret_x, _) = preedit_layout.index_to_position(caret);
+
+                quads.merge(&mut DefaultMesh::quad(
+                    (preedit_origin.0 + caret_x, preedit_origin.1, z + 3.5),
+                    (thickness, line_height),
+                    (0.0, 0.0, 0.0, 0.0),
+                    self.color,
+                ));
+            }
+
+            meshes.extend(DefaultMesh::<DefaultVertex>::text_layout(
+                (preedit_origin.0, preedit_origin.1, z + 3.0),
+                &preedit_layout,
+                font,
+                self.color,
+            ));
+        }
+
+        let mut commands = Vec::new();
+
+        if !quads.verts.is_empty() {
+            quads.possibly_trasparent();
+
+            let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", plain_shader, "fs_main")?;
+
+            let mut command = R::RenderCommand::new(R::Mesh::convert(&quads), shader);
+            command.add_uniform(ctx.app.renderer.camera_uniform());
+            commands.push(command);
+        }
+
+        commands.extend(atlas_commands(&mut ctx.app.renderer, font, meshes, plain_shader, text_shader)?);
+
+        Ok(commands)
+    }
+}
+
+/// Lays out every line of the text on its own
+fn layout_paragraphs<R: AnuraRenderer>(font: &FontAtlas<R>, text: &str, options: &TextLayoutOptions) -> Vec<Paragraph> {
+    let mut paragraphs = Vec::new();
+    let mut start = 0;
+    let mut top = 0.0;
+
+    for line in text.split('\n') {
+        let paragraph = Paragraph {
+            start,
+            top,
+            layout: TextLayout::new(font, line, options),
+            dirty: false,
+            ..Paragraph::dirty(line.len())
+        };
+
+        start += line.len() + 1;
+        top += paragraph.height();
+        paragraphs.push(paragraph);
+    }
+
+    paragraphs
+}
+
+/// Lines of the paragraph which are completely inside the text area
+fn lines_in_view(paragraph: &Paragraph, scroll_y: f32, height: f32, size: f32, line_height: f32) -> Range<usize> {
+    let in_view = |line: &LayoutLine| {
+        let top = paragraph.top + line.baseline - size - scroll_y;
+        top >= -0.01 && top + line_height <= height + 0.01
+    };
+
+    let lines = &paragraph.layout.lines;
+    match lines.iter().position(in_view) {
+        Some(first) => first..first + lines[first..].iter().take_while(|line| in_view(line)).count(),
+        None => 0..0,
+    }
+}
+
+/// Glyph meshes of the lines in `key`, glyphs cut off at the sides are left out
+fn paragraph_meshes<R: AnuraRenderer>(
+    paragraph: &Paragraph,
+    key: &MeshKey,
+    font: &mut FontAtlas<R>,
+) -> Vec<(usize, DefaultMesh<DefaultVertex>)> {
+    let layout = &paragraph.layout;
+
+    let mut visible = TextLayout {
+        line_height: layout.line_height,
+        font_size: layout.font_size,
+        ..Default::default()
+    };
+
+    for line in &layout.lines[key.lines.clone()] {
+        let first_glyph = visible.glyphs.len();
+
+        for glyph in &layout.glyphs[line.glyphs.clone()] {
+            let glyph_x = glyph.x - key.scroll_x;
+            if glyph_x < -0.5 || glyph_x + glyph.advance > key.width + 0.5 { continue }
+
+            visible.glyphs.push(PositionedGlyph { x: glyph_x, ..*glyph });
+        }
+
+        visible.lines.push(LayoutLine {
+            glyphs: first_glyph..visible.glyphs.len(),
+            x: line.x - key.scroll_x,
+            visible: true,
+            ..line.clone()
+        });
+    }
+
+    DefaultMesh::<DefaultVertex>::text_layout((0.0, 0.0, 0.0), &visible, font, key.color)
+}
+
+/// Index of the paragraph a byte index is in
+fn paragraph_of(paragraphs: &[Paragraph], index: usize) -> usize {
+    paragraphs
+        .iter()
+        .rposition(|paragraph| paragraph.start <= index)
+        .unwrap_or(0)
+}
+
+/// Cursor position in front of a byte index, relative to the top left of the text
+fn index_to_point(paragraphs: &[Paragraph], index: usize) -> (f32, f32) {
+    let paragraph = &paragraphs[paragraph_of(paragraphs, index)];
+    let (x, top) = paragraph.layout.index_to_position(index - paragraph.start);
+
+    (x, paragraph.top + top)
+}
+
+/// Byte index closest to a point relative to the top left of the text
+fn point_to_index(paragraphs: &[Paragraph], (x, y): (f32, f32)) -> usize {
+    let paragraph = match paragraphs.iter().rposition(|paragraph| paragraph.top <= y) {
+        Some(index) => &paragraphs[index],
+        None => match paragraphs.first() {
+            Some(paragraph) => paragraph,
+            None => return 0,
+        },
+    };
+
+    paragraph.start + paragraph.layout.position_to_index((x, y - paragraph.top))
+}
+
+fn content_size(paragraphs: &[Paragraph]) -> (f32, f32) {
+    let width = paragraphs.iter().map(|paragraph| paragraph.layout.size.0).fold(0.0, f32::max);
+    let height = paragraphs.last().map_or(0.0, |paragraph| {
+        paragraph.top + paragraph.layout.lines.len() as f32 * paragraph.layout.line_height
+    });
+
+    (width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::MemoryClipboard;
+
+    #[test]
+    fn copy_cut_paste_keep_line_breaks() {
+        let mut clipboard = MemoryClipboard::new();
+        let mut area = TextArea { text: "one\ntwo\nthree".to_owned(), ..Default::default() };
+
+        area.select(0, 7);
+        assert!(area.copy(&mut clipboard));
+        assert_eq!(clipboard.get_text().as_deref(), Some("one\ntwo"));
+
+        assert!(area.cut(&mut clipboard));
+        assert_eq!(area.text, "\nthree");
+
+        area.select(area.text.len(), area.text.len());
+        assert!(area.paste(&mut clipboard));
+        assert_eq!(area.text, "\nthreeone\ntwo");
+    }
+
+    #[test]
+    fn paste_is_undone_in_one_step() {
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("a\nb");
+
+        let mut area = TextArea { text: "xy".to_owned(), ..Default::default() };
+        area.select(1, 1);
+
+        assert!(area.paste(&mut clipboard));
+        assert_eq!(area.text, "xa\nby");
+
+        assert!(area.undo());
+        assert_eq!(area.text, "xy");
+    }
+
+    /// Paragraphs as if `text` was just laid out
+    fn laid_out(text: &str) -> TextArea {
+        let mut area = TextArea { text: text.to_owned(), ..Default::default() };
+        area.state.text = text.to_owned();
+
+        let mut start = 0;
+        for line in text.split('\n') {
+            area.state.paragraphs.push(Paragraph { start, dirty: false, ..Paragraph::dirty(line.len()) });
+            start += line.len() + 1;
+        }
+
+        area
+    }
+
+    fn dirty(area: &TextArea) -> Vec<(usize, usize, bool)> {
+        area.state.paragraphs.iter().map(|paragraph| (paragraph.start, paragraph.len, paragraph.dirty)).collect()
+    }
+
+    #[test]
+    fn edits_only_dirty_touched_paragraphs() {
+        let mut area = laid_out("one\ntwo\nthree");
+
+        area.select(5, 5);
+        area.replace(5..5, "\nx", EditKind::Other);
+        assert_eq!(area.text, "one\nt\nxwo\nthree");
+        assert_eq!(dirty(&area), vec![(0, 3, false), (4, 1, true), (6, 3, true), (10, 5, false)]);
+
+        area.replace(2..7, "", EditKind::Other);
+        assert_eq!(area.text, "onwo\nthree");
+        assert_eq!(dirty(&area), vec![(0, 4, true), (5, 5, false)]);
+
+        assert!(area.undo());
+        assert_eq!(area.state.text, area.text);
+        assert_eq!(dirty(&area), vec![(0, 3, true), (4, 1, true), (6, 3, true), (10, 5, false)]);
+    }
+
+    #[test]
+    fn nothing_selected_copies_nothing() {
+        let mut clipboard = MemoryClipboard::new();
+        let mut area = TextArea { text: "text".to_owned(), ..Default::default() };
+
+        assert!(!area.copy(&mut clipboard));
+        assert!(!area.cut(&mut clipboard));
+        assert_eq!(clipboard.get_text(), None);
+        assert!(!area.paste(&mut clipboard));
+    }
+}
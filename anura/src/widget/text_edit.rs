@@ -0,0 +1,317 @@
+use std::{
+    ops::Range,
+    time::Duration,
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How long the caret stays visible and then hidden while blinking
+const CARET_BLINK: Duration = Duration::from_millis(530);
+/// Typing or deleting within this time of the last edit gets undone together with it
+const COALESCE_TIME: Duration = Duration::from_millis(1000);
+
+/// Bookkeeping of editable text widgets which is only touched by the widget itself
+pub struct EditState {
+    /// How far the text is scrolled to keep the caret in view
+    pub(crate) scroll: (f32, f32),
+    /// Frame time the caret last got shown at, see `InputManager::now`
+    pub(crate) blink_start: Duration,
+    pub(crate) caret_visible: bool,
+    /// Frame time of the last event, edits made through methods get stamped with it
+    pub(crate) now: Duration,
+    pub(crate) was_focused: bool,
+    pub(crate) dragging: bool,
+    pub(crate) preedit: Option<Preedit>,
+    /// Bottom of the caret relative to the widget, where the ime's candidate window goes
+    pub(crate) ime_offset: (f32, f32),
+}
+
+impl Default for EditState {
+    fn default() -> Self {
+        Self {
+            scroll: (0.0, 0.0),
+            blink_start: Duration::ZERO,
+            caret_visible: true,
+            now: Duration::ZERO,
+            was_focused: false,
+            dragging: false,
+            preedit: None,
+            ime_offset: (0.0, 0.0),
+        }
+    }
+}
+
+impl EditState {
+    /// Returns true if the focus changed since the last call
+    pub(crate) fn update_focus(&mut self, focused: bool) -> bool {
+        let changed = focused != self.was_focused;
+        self.was_focused = focused;
+
+        if !focused {
+            self.preedit = None;
+        }
+
+        changed
+    }
+
+    /// Shows what the ime is composing, an empty text removes it again
+    pub(crate) fn set_preedit(&mut self, text: String, cursor: Option<(usize, usize)>) {
+        self.preedit = match text.is_empty() {
+            true => None,
+            false => Some(Preedit { text, cursor }),
+        };
+        self.reset_blink(self.now);
+    }
+
+    /// Shows the caret right away, called after anything moved it
+    pub(crate) fn reset_blink(&mut self, now: Duration) {
+        self.blink_start = now;
+        self.caret_visible = true;
+    }
+
+    /// Returns true if the caret just appeared or disappeared
+    pub(crate) fn update_blink(&mut self, now: Duration) -> bool {
+        let blinks = now.saturating_sub(self.blink_start).as_millis() / CARET_BLINK.as_millis();
+        let visible = blinks.is_multiple_of(2);
+
+        let changed = visible != self.caret_visible;
+        self.caret_visible = visible;
+        changed
+    }
+}
+
+/// Text being composed with an input method, drawn at the caret until it gets committed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preedit {
+    pub text: String,
+    /// Byte range of the ime's cursor inside `text`, None hides the caret
+    pub cursor: Option<(usize, usize)>,
+}
+
+impl Preedit {
+    /// Where the caret goes inside the composed text
+    pub(crate) fn caret(&self) -> Option<usize> {
+        self.cursor.map(|(start, _)| start.min(self.text.len()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    Typing,
+    Deleting,
+    /// Pasting, replacing a selection and the like, never merged with other edits
+    Other,
+}
+
+/// A single change to the text, enough to apply it in both directions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    /// Byte index the change starts at
+    pub at: usize,
+    pub removed: String,
+    pub inserted: String,
+    /// Caret and anchor before the edit
+    pub selection_before: (usize, usize),
+    pub kind: EditKind,
+    /// Frame time the edit was made at, see `InputManager::now`
+    time: Duration,
+}
+
+impl Edit {
+    pub fn new(at: usize, removed: String, inserted: String, selection_before: (usize, usize), kind: EditKind, time: Duration) -> Self {
+        Self {
+            at,
+            removed,
+            inserted,
+            selection_before,
+            kind,
+            time,
+        }
+    }
+
+    /// Tries to fold `next` into this edit so both get undone in one go
+    fn coalesce(&mut self, next: &Edit) -> bool {
+        if self.kind != next.kind || next.time.saturating_sub(self.time) > COALESCE_TIME {
+            return false;
+        }
+
+        match self.kind {
+            EditKind::Typing => {
+                let contiguous = next.removed.is_empty() && self.at + self.inserted.len() == next.at;
+
+                // Every word gets its own undo step
+                let new_word = self.inserted.ends_with(char::is_whitespace)
+                    && !next.inserted.starts_with(char::is_whitespace);
+
+                if !contiguous || new_word { return false }
+
+                self.inserted.push_str(&next.inserted);
+            }
+            EditKind::Deleting => {
+                if !next.inserted.is_empty() { return false }
+
+                if next.at + next.removed.len() == self.at {
+                    // Backspace
+                    self.removed.insert_str(0, &next.removed);
+                    self.at = next.at;
+                } else if next.at == self.at {
+                    // Delete
+                    self.removed.push_str(&next.removed);
+                } else {
+                    return false;
+                }
+            }
+            EditKind::Other => return false,
+        }
+
+        self.time = next.time;
+        true
+    }
+}
+
+/// Undo/redo stacks, consecutive typing and deleting gets merged into single steps
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Oldest steps get dropped past this
+    pub limit: usize,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit: 500,
+        }
+    }
+}
+
+impl EditHistory {
+    /// Adds an edit which was already applied to the text
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+
+        if let Some(last) = self.undo.last_mut() {
+            if last.coalesce(&edit) { return }
+        }
+
+        self.undo.push(edit);
+
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Reverts the last edit, returns the caret and anchor to restore
+    ///
+    /// The history gets cleared if the text was changed without it knowing
+    pub fn undo(&mut self, text: &mut String) -> Option<(usize, usize)> {
+        let edit = self.undo.pop()?;
+
+        if text.get(edit.at..edit.at + edit.inserted.len()) != Some(edit.inserted.as_str()) {
+            self.clear();
+            return None;
+        }
+
+        text.replace_range(edit.at..edit.at + edit.inserted.len(), &edit.removed);
+        let selection = edit.selection_before;
+
+        self.redo.push(edit);
+        Some(selection)
+    }
+
+    /// Applies the last undone edit again, returns the caret and anchor to restore
+    pub fn redo(&mut self, text: &mut String) -> Option<(usize, usize)> {
+        let edit = self.redo.pop()?;
+
+        if text.get(edit.at..edit.at + edit.removed.len()) != Some(edit.removed.as_str()) {
+            self.clear();
+            return None;
+        }
+
+        text.replace_range(edit.at..edit.at + edit.removed.len(), &edit.inserted);
+        let caret = edit.at + edit.inserted.len();
+
+        self.undo.push(edit);
+        Some((caret, caret))
+    }
+
+    /// The edit `undo` would revert
+    pub fn next_undo(&self) -> Option<&Edit> {
+        self.undo.last()
+    }
+
+    /// The edit `redo` would apply again
+    pub fn next_redo(&self) -> Option<&Edit> {
+        self.redo.last()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// Moves an index back onto the text if its out of bounds or in the middle of a char
+pub(crate) fn clamp_index(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Arrow keys and the like come in as private use characters on macos
+pub(crate) fn is_private_use(c: char) -> bool {
+    ('\u{f700}'..='\u{f8ff}').contains(&c)
+}
+
+pub(crate) fn prev_grapheme(text: &str, index: usize) -> usize {
+    text[..index].grapheme_indices(true).next_back().map_or(0, |(i, _)| i)
+}
+
+pub(crate) fn next_grapheme(text: &str, index: usize) -> usize {
+    text[index..].graphemes(true).next().map_or(index, |grapheme| index + grapheme.len())
+}
+
+/// Start of the word before the index, whitespace in between is skipped
+pub(crate) fn prev_word(text: &str, index: usize) -> usize {
+    text[..index]
+        .split_word_bound_indices()
+        .rev()
+        .find(|(_, word)| !word.trim().is_empty())
+        .map_or(0, |(i, _)| i)
+}
+
+/// End of the word after the index, whitespace in between is skipped
+pub(crate) fn next_word(text: &str, index: usize) -> usize {
+    text[index..]
+        .split_word_bound_indices()
+        .find(|(_, word)| !word.trim().is_empty())
+        .map_or(text.len(), |(i, word)| index + i + word.len())
+}
+
+/// Byte range of the word (or run of whitespace/punctuation) the index is in
+pub(crate) fn word_at(text: &str, index: usize) -> Range<usize> {
+    text.split_word_bound_indices()
+        .map(|(i, word)| i..i + word.len())
+        .find(|range| range.contains(&index))
+        .unwrap_or(index..index)
+}
+
+/// Byte range of the line the index is in, without the newline
+pub(crate) fn line_at(text: &str, index: usize) -> Range<usize> {
+    let start = text[..index].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[index..].find('\n').map_or(text.len(), |i| index + i);
+    start..end
+}
@@ -0,0 +1,671 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::{
+    app::App,
+    clipboard::Clipboard,
+    context::{AnuraContext, RenderContext},
+    error::AnuraError,
+    event::WidgetEvent,
+    font::{Font, FontManager, TextLayout, TextLayoutOptions, TextWrap, VerticalAlign},
+    graphics::{AnuraRenderCommand, AnuraRenderer},
+    handle::NodeType,
+    prelude::{DefaultMesh, DefaultVertex, FromAnuraMesh, FromAnuraUniform},
+    view::View,
+    widget::Layout,
+};
+
+use super::{
+    atlas_commands, clamp_index, is_private_use, next_grapheme, next_word, prev_grapheme,
+    prev_word, word_at, EditState, Widget,
+};
+
+/// See `TextInput::validator`
+pub type Validator = Box<dyn Fn(&str) -> bool>;
+
+/// Single line of editable text
+///
+/// Clicking into the input focuses it, after that it takes keyboard input until
+/// something else gets clicked
+pub struct TextInput {
+    pub text: String,
+    /// Shown while the input is empty
+    pub placeholder: String,
+    pub font: Font,
+    pub color: (f32, f32, f32, f32),
+    pub placeholder_color: (f32, f32, f32, f32),
+    pub selection_color: (f32, f32, f32, f32),
+    pub background: Option<(f32, f32, f32, f32)>,
+    /// Space between the edges and the text
+    pub padding: f32,
+    /// Every character is shown as this one, e.g. `Some('•')` for passwords
+    pub mask: Option<char>,
+    /// Maximum length in chars, typed text which doesnt fit anymore gets cut off
+    pub max_length: Option<usize>,
+    /// Gets what the text would be after an edit, returning false rejects the edit
+    pub validator: Option<Validator>,
+    /// Byte index of the caret, see `select` for moving it
+    pub caret: usize,
+    /// Where the selection started, same as `caret` if nothing is selected
+    pub anchor: usize,
+    pub state: EditState,
+}
+
+impl Default for TextInput {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            placeholder: String::new(),
+            font: Font::Default,
+            color: (0.0, 0.0, 0.0, 1.0),
+            placeholder_color: (0.5, 0.5, 0.5, 1.0),
+            selection_color: (0.6, 0.75, 1.0, 1.0),
+            background: Some((1.0, 1.0, 1.0, 1.0)),
+            padding: 4.0,
+            mask: None,
+            max_length: None,
+            validator: None,
+            caret: 0,
+            anchor: 0,
+            state: EditState::default(),
+        }
+    }
+}
+
+impl TextInput {
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Byte range of the selected text, empty if nothing is selected
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Selects from `anchor` to `caret`, both are byte indices and get clamped to the text
+    pub fn select(&mut self, anchor: usize, caret: usize) {
+        self.anchor = clamp_index(&self.text, anchor);
+        self.caret = clamp_index(&self.text, caret);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0, self.text.len());
+    }
+
+    /// Replaces the selection with `text` as if it was typed
+    ///
+    /// Returns false if nothing changed, e.g. because the validator rejected it
+    pub fn insert(&mut self, text: &str) -> bool {
+        // Single line, so line breaks and other control characters have no business here
+        let text = text
+            .chars()
+            .filter(|c| !c.is_control() && !is_private_use(*c))
+            .collect::<String>();
+
+        self.replace(self.selection(), &text)
+    }
+
+    /// Copies the selection, masked inputs never give their text away
+    pub fn copy(&self, clipboard: &mut dyn Clipboard) -> bool {
+        if self.mask.is_some() || self.selection().is_empty() { return false }
+
+        clipboard.set_text(self.selected_text());
+        true
+    }
+
+    /// Copies the selection and deletes it, returns true if the text changed
+    pub fn cut(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        if !self.copy(clipboard) { return false }
+
+        self.replace(self.selection(), "")
+    }
+
+    /// Replaces the selection with the clipboard's text, line breaks become spaces
+    pub fn paste(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        match clipboard.get_text() {
+            Some(text) => self.insert(&text.lines().collect::<Vec<_>>().join(" ")),
+            None => false,
+        }
+    }
+
+    fn replace(&mut self, range: Range<usize>, insert: &str) -> bool {
+        let mut insert = insert;
+
+        if let Some(max_length) = self.max_length {
+            let kept = self.text.chars().count() - self.text[range.clone()].chars().count();
+            let room = max_length.saturating_sub(kept);
+
+            if let Some((end, _)) = insert.char_indices().nth(room) {
+                insert = &insert[..end];
+            }
+        }
+
+        if range.is_empty() && insert.is_empty() { return false }
+
+        let mut new_text = String::with_capacity(self.text.len() - range.len() + insert.len());
+        new_text.push_str(&self.text[..range.start]);
+        new_text.push_str(insert);
+        new_text.push_str(&self.text[range.end..]);
+
+        if let Some(validator) = &self.validator {
+            if !validator(&new_text) { return false }
+        }
+
+        self.text = new_text;
+        self.caret = range.start + insert.len();
+        self.anchor = self.caret;
+
+        true
+    }
+
+    /// Deletes the selection, or the text between the caret and `to` if nothing is selected
+    fn delete_towards(&mut self, to: usize) -> bool {
+        let range = match self.selection() {
+            selection if !selection.is_empty() => selection,
+            _ => self.caret.min(to)..self.caret.max(to),
+        };
+
+        self.replace(range, "")
+    }
+
+    fn move_caret(&mut self, to: usize, select: bool) {
+        self.caret = to;
+        if !select {
+            self.anchor = to;
+        }
+    }
+
+    /// What actually gets drawn, with the ime's composition at the caret.
+    /// The placeholder is not included
+    fn display_text(&self) -> String {
+        let mut text = match self.mask {
+            Some(mask) => self.text.graphemes(true).map(|_| mask).collect(),
+            None => self.text.clone(),
+        };
+
+        if let Some(preedit) = &self.state.preedit {
+            text.insert_str(self.masked(self.caret), &preedit.text);
+        }
+
+        text
+    }
+
+    /// Byte index into the text to byte index into the masked text
+    fn masked(&self, index: usize) -> usize {
+        match self.mask {
+            Some(mask) => self.text[..index].graphemes(true).count() * mask.len_utf8(),
+            None => index,
+        }
+    }
+
+    fn unmasked(&self, index: usize) -> usize {
+        match self.mask {
+            Some(mask) => self.text
+                .grapheme_indices(true)
+                .nth(index / mask.len_utf8())
+                .map_or(self.text.len(), |(i, _)| i),
+            None => index,
+        }
+    }
+
+    fn preedit_len(&self) -> usize {
+        self.state.preedit.as_ref().map_or(0, |preedit| preedit.text.len())
+    }
+
+    /// Byte index into the text to byte index into the displayed text
+    fn to_display(&self, index: usize) -> usize {
+        match index > self.caret {
+            true => self.masked(index) + self.preedit_len(),
+            false => self.masked(index),
+        }
+    }
+
+    /// Byte index into the displayed text to byte index into the text
+    fn index_from_display(&self, index: usize) -> usize {
+        let caret = self.masked(self.caret);
+        let preedit_len = self.preedit_len();
+
+        // Anything inside the composition belongs to the caret
+        if index <= caret {
+            self.unmasked(index)
+        } else if index <= caret + preedit_len {
+            self.caret
+        } else {
+            self.unmasked(index - preedit_len)
+        }
+    }
+
+    /// Where the caret is in the displayed text, None if the ime hides it
+    fn display_caret(&self) -> Option<usize> {
+        match &self.state.preedit {
+            Some(preedit) => preedit.caret().map(|caret| self.masked(self.caret) + caret),
+            None => Some(self.masked(self.caret)),
+        }
+    }
+
+    /// Scrolls just far enough for the caret to be in view, also moves the ime along with it
+    fn scroll_to_caret<R: AnuraRenderer>(
+        &mut self,
+        font_manager: &FontManager<R>,
+        (width, height): (f32, f32),
+    ) -> Result<(), AnuraError<R::ErrorMessage>> {
+        let display_layout = font_manager.layout(&self.font, &self.display_text(), &self.layout_options(height))?;
+        let caret = self.display_caret().unwrap_or_else(|| self.to_display(self.caret));
+        let (caret_x, top) = display_layout.index_to_position(caret);
+        let inner_width = (width - self.padding * 2.0).max(0.0);
+
+        let scroll = &mut self.state.scroll.0;
+        if caret_x - *scroll > inner_width {
+            *scroll = caret_x - inner_width;
+        } else if caret_x < *scroll {
+            *scroll = caret_x;
+        }
+
+        // Dont leave empty space on the right after deleting
+        let content_width = display_layout.size.0.max(caret_x);
+        *scroll = scroll.min((content_width - inner_width).max(0.0)).max(0.0);
+
+        self.state.ime_offset = (self.padding + caret_x - *scroll, top + display_layout.line_height);
+
+        Ok(())
+    }
+
+    fn layout_options(&self, height: f32) -> TextLayoutOptions {
+        TextLayoutOptions {
+            max_height: Some(height),
+            vertical_align: VerticalAlign::Center,
+            wrap: TextWrap::None,
+            ..Default::default()
+        }
+    }
+
+    fn handle_keys(&mut self, keys: &[VirtualKeyCode], (shift, ctrl, command): (bool, bool, bool), clipboard: &mut dyn Clipboard) -> bool {
+        let mut changed = false;
+
+        for key in keys {
+            let (text, caret) = (&self.text, self.caret);
+
+            let prev = if ctrl { prev_word(text, caret) } else { prev_grapheme(text, caret) };
+            let next = if ctrl { next_word(text, caret) } else { next_grapheme(text, caret) };
+            let selection = self.selection();
+
+            match key {
+                // Without shift the selection collapses to the side the caret moved to
+                VirtualKeyCode::Left if !shift && !selection.is_empty() => self.move_caret(selection.start, false),
+                VirtualKeyCode::Right if !shift && !selection.is_empty() => self.move_caret(selection.end, false),
+                VirtualKeyCode::Left => self.move_caret(prev, shift),
+                VirtualKeyCode::Right => self.move_caret(next, shift),
+                VirtualKeyCode::Home => self.move_caret(0, shift),
+                VirtualKeyCode::End => self.move_caret(self.text.len(), shift),
+                VirtualKeyCode::A if command => self.select_all(),
+                VirtualKeyCode::C if command => { self.copy(clipboard); }
+                VirtualKeyCode::X if command => { self.cut(clipboard); }
+                VirtualKeyCode::V if command => { self.paste(clipboard); }
+                VirtualKeyCode::Back => { self.delete_towards(prev); }
+                VirtualKeyCode::Delete => { self.delete_towards(next); }
+                _ => continue,
+            }
+
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+impl<A, V, R> Widget<A, V, R> for TextInput
+where
+    A: App<R> + 'static,
+    V: View<A, R> + 'static,
+    R: AnuraRenderer + 'static,
+    R::Mesh: FromAnuraMesh,
+    R::Uniform: FromAnuraUniform,
+    R::RenderCommand: AnuraRenderCommand<Renderer = R>
+{
+    fn handle_event(
+        &mut self,
+        ctx: &mut AnuraContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        event: WidgetEvent,
+    ) -> Result<(), AnuraError<R::ErrorMessage>> {
+        let now = ctx.app.input_manager.now();
+        self.state.now = now;
+
+        match event {
+            WidgetEvent::Init => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+            }
+            WidgetEvent::Update => {
+                ctx.app.font_manager.load_font(&self.font, false)?;
+
+                // The text might have been changed from the outside
+                self.caret = clamp_index(&self.text, self.caret);
+                self.anchor = clamp_index(&self.text, self.anchor);
+
+                let mut changed = false;
+
+                // Nothing can be clicked before the first render
+                let layout = match ctx.layout() {
+                    Some(layout) => layout,
+                    None => return Ok(()),
+                };
+
+                let (x, y, _) = layout.origin;
+                let (width, height) = layout.available_space;
+
+                let input = &ctx.app.input_manager;
+                let mouse = (input.mouse_position.x as f32, input.mouse_position.y as f32);
+                let hovered = mouse.0 >= x && mouse.0 <= x + width && mouse.1 >= y && mouse.1 <= y + height;
+
+                let (shift, ctrl, command) = (input.shift(), input.ctrl(), input.command());
+                let just_pressed = input.just_pressed(MouseButton::Left);
+
+                if just_pressed {
+                    if hovered {
+                        ctx.focus();
+                        self.state.dragging = true;
+                    } else if ctx.is_focused() {
+                        ctx.ui_tree.blur();
+                    }
+                }
+
+                let focused = ctx.is_focused();
+                changed |= self.state.update_focus(focused);
+
+                if self.state.dragging {
+                    let display_layout = ctx.app.font_manager.layout(&self.font, &self.display_text(), &self.layout_options(height))?;
+                    let index = display_layout.position_to_index((mouse.0 - x - self.padding + self.state.scroll.0, mouse.1 - y));
+                    let index = self.index_from_display(index);
+
+                    // Only the first click can extend an existing selection
+                    let clicks = match just_pressed {
+                        true => ctx.app.input_manager.click_count(MouseButton::Left),
+                        false => 0,
+                    };
+
+                    // Words of a masked text shouldnt be guessable, so double clicks select everything there
+                    if clicks == 3 || (clicks == 2 && self.mask.is_some()) {
+                        self.select_all();
+                        self.state.dragging = false;
+                        changed = true;
+                    } else if clicks == 2 {
+                        let word = word_at(&self.text, index);
+                        self.select(word.start, word.end);
+                        self.state.dragging = false;
+                        changed = true;
+                    } else if index != self.caret || just_pressed {
+                        self.move_caret(index, shift || !just_pressed);
+                        changed = true;
+                    }
+                }
+
+                // Checked after the click so clicks which are let go within a frame still count
+                if !ctx.app.input_manager.is_down(MouseButton::Left) {
+                    self.state.dragging = false;
+                }
+
+                // Keys are meant for the ime while its composing
+                if focused && self.state.preedit.is_none() {
+                    let keys = ctx.app.input_manager.key_presses.clone();
+                    changed |= self.handle_keys(&keys, (shift, ctrl, command), &mut *ctx.app.clipboard);
+
+                    let typed = ctx.app.input_manager.received_chars.iter().collect::<String>();
+                    if !typed.is_empty() {
+                        changed |= self.insert(&typed);
+                    }
+                }
+
+                if changed {
+                    self.state.reset_blink(now);
+                    self.scroll_to_caret(&ctx.app.font_manager, layout.available_space)?;
+                }
+
+                if focused {
+                    changed |= self.state.update_blink(now);
+
+                    // Passwords shouldnt end up in the ime's dictionary
+                    if self.mask.is_none() {
+                        let offset = self.state.ime_offset;
+                        ctx.app.request_ime((x + offset.0, y + offset.1));
+                    }
+                }
+
+                if changed {
+                    ctx.issue_rerender();
+                }
+            }
+            // Masked inputs never ask for the ime, anything it still sends them is dropped
+            WidgetEvent::ImePreedit { text, cursor } => {
+                if self.mask.is_some() { return Ok(()) }
+
+                self.state.set_preedit(text, cursor);
+
+                if let Some(layout) = ctx.layout() {
+                    self.scroll_to_caret(&ctx.app.font_manager, layout.available_space)?;
+                }
+                ctx.issue_rerender();
+            }
+            WidgetEvent::ImeCommit(text) => {
+                if self.mask.is_some() || !self.insert(&text) { return Ok(()) }
+
+                self.state.reset_blink(now);
+
+                if let Some(layout) = ctx.layout() {
+                    self.scroll_to_caret(&ctx.app.font_manager, layout.available_space)?;
+                }
+                ctx.issue_rerender();
+            }
+            WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
+        };
+
+        Ok(())
+    }
+
+    fn generate_mesh(
+        &self,
+        ctx: &mut RenderContext<'_, '_, A, V, R>,
+        _view: &mut V,
+        layout: Layout,
+    ) -> Result<Vec<R::RenderCommand>, AnuraError<R::ErrorMessage>> {
+        let plain_shader = crate::graphics::PLAIN_SHADER;
+        let text_shader = crate::graphics::TEXT_SHADER;
+
+        let (x, y, z) = layout.origin;
+        let (width, height) = layout.available_space;
+        let inner_width = (width - self.padding * 2.0).max(0.0);
+
+        let focused = match ctx.current {
+            NodeType::Handle(handle) => ctx.ui_tree.is_focused(&handle),
+            NodeType::Root => false,
+        };
+        let options = self.layout_options(height);
+
+        let font = ctx.app.font_manager.get_font_mut(&self.font)?;
+        let thickness = (font.size / 16.0).max(1.0);
+
+        let text_layout = TextLayout::new(font, &self.display_text(), &options);
+
+        let mut quads = DefaultMesh::<DefaultVertex>::empty(z);
+
+        if let Some(background) = self.background {
+            quads.merge(&mut DefaultMesh::quad((x, y, z), (width, height), (0.0, 0.0, 0.0, 0.0), background));
+        }
+
+        let text_x = x + self.padding;
+        // Screen x of a byte index into the displayed text, clamped to the inside of the input
+        let caret_x = |index: usize| {
+            let (caret_x, top) = text_layout.index_to_position(index);
+            ((caret_x - self.state.scroll.0).clamp(0.0, inner_width), top)
+        };
+
+        let selection = self.selection();
+        if focused && !selection.is_empty() {
+            let (start, top) = caret_x(self.to_display(selection.start));
+            let (end, _) = caret_x(self.to_display(selection.end));
+
+            quads.merge(&mut DefaultMesh::quad(
+                (text_x + start, y + top, z + 0.5),
+                (end - start, text_layout.line_height),
+                (0.0, 0.0, 0.0, 0.0),
+                self.selection_color,
+            ));
+        }
+
+        if let (true, Some(caret)) = (focused && self.state.caret_visible, self.display_caret()) {
+            let (caret, top) = caret_x(caret);
+
+            quads.merge(&mut DefaultMesh::quad(
+                (text_x + caret, y + top, z + 1.5),
+                (thickness, text_layout.line_height),
+                (0.0, 0.0, 0.0, 0.0),
+                self.color,
+            ));
+        }
+
+        // The composition is underlined to set it apart from the actual text
+        if let (true, Some(line)) = (self.state.preedit.is_some(), text_layout.lines.first()) {
+            let start = self.masked(self.caret);
+            let (start_x, _) = caret_x(start);
+            let (end_x, _) = caret_x(start + self.preedit_len());
+
+            quads.merge(&mut DefaultMesh::quad(
+                (text_x + start_x, y + line.baseline + thickness, z + 1.0),
+                (end_x - start_x, thickness),
+                (0.0, 0.0, 0.0, 0.0),
+                self.color,
+            ));
+        }
+
+        let (mut text_layout, color) = match self.text.is_empty() && self.state.preedit.is_none() {
+            true => (TextLayout::new(font, &self.placeholder, &options), self.placeholder_color),
+            false => (text_layout, self.color),
+        };
+
+        // Scroll and cut off glyphs which arent completely inside, theres only a single line
+        for glyph in &mut text_layout.glyphs {
+            glyph.x -= self.state.scroll.0;
+        }
+        text_layout.glyphs.retain(|glyph| glyph.x >= -0.5 && glyph.x + glyph.advance <= inner_width + 0.5);
+        if let Some(line) = text_layout.lines.first_mut() {
+            line.glyphs = 0..text_layout.glyphs.len();
+        }
+
+        let meshes = DefaultMesh::<DefaultVertex>::text_layout((text_x, y, z), &text_layout, font, color);
+
+        let mut commands = Vec::new();
+
+        if !quads.verts.is_empty() {
+            quads.possibly_trasparent();
+
+            let shader = ctx.app.renderer.load_shader(plain_shader, "vs_main", plain_shader, "fs_main")?;
+
+            let mut command = R::RenderCommand::new(R::Mesh::convert(&quads), shader);
+            command.add_uniform(ctx.app.renderer.camera_uniform());
+            commands.push(command);
+        }
+
+        commands.extend(atlas_commands(&mut ctx.app.renderer, font, meshes, plain_shader, text_shader)?);
+
+        Ok(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::MemoryClipboard;
+
+    fn input(text: &str) -> TextInput {
+        let mut input = TextInput { text: text.to_owned(), ..Default::default() };
+        input.select_all();
+        input
+    }
+
+    #[test]
+    fn copy_cut_paste() {
+        let mut clipboard = MemoryClipboard::new();
+        let mut input = input("hello world");
+
+        input.select(0, 5);
+        assert!(input.copy(&mut clipboard));
+        assert_eq!(clipboard.get_text().as_deref(), Some("hello"));
+
+        input.select(5, 11);
+        assert!(input.cut(&mut clipboard));
+        assert_eq!(input.text, "hello");
+        assert_eq!(clipboard.get_text().as_deref(), Some(" world"));
+
+        input.select(0, 0);
+        assert!(input.paste(&mut clipboard));
+        assert_eq!(input.text, " worldhello");
+        assert_eq!(input.caret(), 6);
+    }
+
+    #[test]
+    fn shortcuts_use_the_clipboard() {
+        let mut clipboard = MemoryClipboard::new();
+        let mut input = input("abc");
+
+        let keys = [VirtualKeyCode::C, VirtualKeyCode::Right, VirtualKeyCode::V];
+        assert!(input.handle_keys(&keys, (false, false, true), &mut clipboard));
+        assert_eq!(input.text, "abcabc");
+
+        // Without the command modifier C and V are left to `received_chars`
+        input.select_all();
+        input.handle_keys(&[VirtualKeyCode::X], (false, false, false), &mut clipboard);
+        assert_eq!(input.text, "abcabc");
+    }
+
+    #[test]
+    fn masked_input_refuses_copy() {
+        let mut clipboard = MemoryClipboard::new();
+        let mut input = TextInput { mask: Some('•'), ..input("secret") };
+
+        assert!(!input.copy(&mut clipboard));
+        assert!(!input.cut(&mut clipboard));
+        assert_eq!(clipboard.get_text(), None);
+        assert_eq!(input.text, "secret");
+    }
+
+    #[test]
+    fn paste_flattens_line_breaks() {
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("one\ntwo\r\nthree");
+
+        let mut input = input("");
+        assert!(input.paste(&mut clipboard));
+        assert_eq!(input.text, "one two three");
+    }
+
+    #[test]
+    fn validator_rejects_paste() {
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("12a");
+
+        let mut input = TextInput {
+            validator: Some(Box::new(|text| text.chars().all(|c| c.is_ascii_digit()))),
+            ..input("7")
+        };
+
+        assert!(!input.paste(&mut clipboard));
+        assert_eq!(input.text, "7");
+
+        clipboard.set_text("42");
+        assert!(input.paste(&mut clipboard));
+        assert_eq!(input.text, "42");
+    }
+}
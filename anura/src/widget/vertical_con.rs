@@ -43,7 +43,18 @@ where
     ) -> Result<(), AnuraError<R::ErrorMessage>> {
         match event {
             WidgetEvent::Update => {}
-            _ => {}
+            WidgetEvent::Init
+            | WidgetEvent::AnimationFinished(_)
+            | WidgetEvent::TaskFinished { .. }
+            | WidgetEvent::ImePreedit { .. }
+            | WidgetEvent::ImeCommit(_)
+            | WidgetEvent::Action(_)
+            | WidgetEvent::DragEnter(_)
+            | WidgetEvent::DragOver(_)
+            | WidgetEvent::DragLeave(_)
+            | WidgetEvent::Drop(_)
+            | WidgetEvent::DragEnd { .. }
+            | WidgetEvent::Gesture(_) => {}
         };
 
         Ok(())
@@ -82,7 +93,7 @@ where
 
         let shader_code = crate::graphics::PLAIN_SHADER;
 
-        let shader = ctx.app.renderer.load_shader(shader_code, "vs_main", shader_code, "fs_main");
+        let shader = ctx.app.renderer.load_shader(shader_code, "vs_main", shader_code, "fs_main")?;
 
         let mesh = DefaultMesh::<DefaultVertex>::quad(
             origin, 
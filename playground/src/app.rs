@@ -14,7 +14,7 @@ impl PlaygroundApp {
 }
 
 impl<R: AnuraRenderer> App<R> for PlaygroundApp {
-    fn handle_event(&mut self, _app: &mut AnuraApp<'_, Self, R>,  event: AppEvent) -> Result<(), AnuraError<R::ErrorMessage>> {
+    fn handle_event(_app: &mut AnuraApp<'_, Self, R>,  event: AppEvent) -> Result<(), AnuraError<R::ErrorMessage>> {
         match event {
             AppEvent::Init => println!("App Init"),
             AppEvent::Exit => println!("App Close"),
@@ -12,14 +12,14 @@ use Anura::prelude::*;
 
 use winit::{
     dpi::PhysicalSize,
-    event_loop::EventLoop,
+    event_loop::{EventLoop, EventLoopBuilder},
     window::{Window, WindowBuilder},
 };
 
 fn main() {
     env_logger::init();
 
-    let event_loop = EventLoop::new();
+    let event_loop = EventLoopBuilder::<AnuraUserEvent>::with_user_event().build();
     let window = WindowBuilder::new()
         .with_min_inner_size(PhysicalSize::new(200, 200))
         .with_title("Playground")
@@ -31,13 +31,14 @@ fn main() {
     pollster::block_on(run(window, event_loop));
 }
 
-async fn run(window: Rc<Window>, event_loop: EventLoop<()>) {
+async fn run(window: Rc<Window>, event_loop: EventLoop<AnuraUserEvent>) {
     // let instance = wgpu::Instance::new(wgpu::Backends::all());
     // let surface = unsafe { instance.create_surface(window.as_ref()) };
 
     let app = PlaygroundApp::new("siema");
 
     let mut Anura_app = AnuraApp::with_default_renderer(app, window).await;
+    Anura_app.task_manager.set_event_loop_proxy(event_loop.create_proxy());
 
     Anura_app.push_view(MainView::default());
 
@@ -10,10 +10,11 @@ pub struct MainView {
 
 #[allow(unused_variables)]
 impl MainView {
-    fn init<A: App<R> + 'static, R: AnuraRenderer + 'static>(&mut self, ui_tree: &mut UiTree<A, Self, R>) {
-        
+    fn init<A: App<R> + 'static, R: AnuraRenderer + 'static>(&mut self, ctx: &mut AnuraContext<'_, '_, A, Self, R>) {
+        ctx.spawn_task(|_| fs::read_to_string("./input.txt").expect("No input file"));
+    }
 
-        let file = fs::read_to_string("./input.txt").expect("No input file");
+    fn build_ui<A: App<R> + 'static, R: AnuraRenderer + 'static>(&mut self, ui_tree: &mut UiTree<A, Self, R>, file: &str) {
 
         let font = Font::Path(PathBuf::from("./LigalexMono.ttf"), 30.0);
 
@@ -125,8 +126,13 @@ impl<A: App<R> + 'static, R: AnuraRenderer + 'static> View<A, R> for MainView {
         event: ViewEvent,
     ) -> Result<(), AnuraError<R::ErrorMessage>>{
         match event {
-            ViewEvent::Init => self.init(ctx.ui_tree),
+            ViewEvent::Init => self.init(ctx),
             ViewEvent::Update => self.update(ctx),
+            ViewEvent::TaskFinished { output, .. } => {
+                if let Ok(file) = output.downcast::<String>() {
+                    self.build_ui(ctx.ui_tree, &file);
+                }
+            }
             _ => {}
         }
 